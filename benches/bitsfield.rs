@@ -0,0 +1,37 @@
+//! Time reading every value of a field with a runtime and a compile time
+//! width, `cargo bench --bench bitsfield`.
+//!
+//! Reads go through `get_unchecked`, as the neighbor count of a step does.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use wasm_game_of_life::game_of_life::{BitsField, BitsFieldConst};
+
+const VALUES: usize = 1 << 20;
+const ROUNDS: u32 = 20;
+
+fn time<F>(name: &str, mut read_all: F)
+where F: FnMut() -> usize {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(read_all());
+    }
+    println!("{:<16} {:>10.3?} per {} reads", name, start.elapsed() / ROUNDS, VALUES);
+}
+
+fn bench<const BITS: usize>() {
+    let mut field = BitsField::<u64>::new(BITS, VALUES).unwrap();
+    field.update(|index, _: u8| (index % 3) as u8);
+    let fixed = BitsFieldConst::<u64, BITS>::from_field(field.clone()).unwrap();
+    let field = black_box(field);
+
+    time(&format!("BitsField<{}>", BITS), || (0..VALUES).map(|index| unsafe { field.get_unchecked::<u8>(index) } as usize).sum());
+    time(&format!("BitsFieldConst<{}>", BITS), || (0..VALUES).map(|index| unsafe { fixed.get_unchecked::<u8>(index) } as usize).sum());
+}
+
+fn main() {
+    bench::<1>();
+    bench::<2>();
+    bench::<4>();
+}
@@ -0,0 +1,27 @@
+//! Time `Universe::step` with every edge behavior, `cargo bench --bench step`.
+//!
+//! Dead and absorbing edges read neighbors through the guard ring, wrapping
+//! and reflecting edges go through `Universe::neighbor_position`.
+
+use std::time::Instant;
+
+use wasm_game_of_life::game_of_life::{rules, EdgeBehavior, InitPolicy, Universe, Xoshiro256};
+
+const SIZE: usize = 256;
+const GENERATIONS: u32 = 20;
+
+fn main() {
+    let life = rules::parse("B3/S23").unwrap();
+    for edges in [EdgeBehavior::Dead, EdgeBehavior::Absorbing{margin: 1}, EdgeBehavior::Wrap, EdgeBehavior::Reflect] {
+        let mut universe = Universe::new(SIZE, SIZE).unwrap();
+        universe.set_edges(edges);
+        universe.initialize(&InitPolicy::Random{alive_probability: 0.3}, &mut Xoshiro256::new(1)).unwrap();
+
+        let start = Instant::now();
+        for _ in 0..GENERATIONS {
+            universe.step(life.as_ref()).unwrap();
+        }
+        let per_generation = start.elapsed() / GENERATIONS;
+        println!("{:<16} {:>10.3?} per generation of {}x{}", edges.to_string(), per_generation, SIZE, SIZE);
+    }
+}
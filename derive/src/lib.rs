@@ -0,0 +1,102 @@
+//! `#[derive(BitFieldCompatible)]` for the state enums stored in a
+//! `wasm_game_of_life::game_of_life::BitsField`.
+//!
+//! Enums must only have unit variants, with integer literal discriminants
+//! or none. Each variant is stored as its discriminant, which must fit in a
+//! byte, or in `bits` bits when given with `#[bitfield(bits = 2)]`. Values
+//! matching no variant decode as the first one.
+
+extern crate proc_macro;
+
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Fields, Lit, LitInt};
+
+#[proc_macro_derive(BitFieldCompatible, attributes(bitfield))]
+pub fn derive_bit_field_compatible(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "BitFieldCompatible can not be derived for generic enums"));
+    }
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => return Err(Error::new_spanned(name, "BitFieldCompatible can only be derived for enums")),
+    };
+    if variants.is_empty() {
+        return Err(Error::new_spanned(name, "BitFieldCompatible needs at least one variant"));
+    }
+    let max = match bits(input)? {
+        Some(bits) => (1u16 << bits) - 1,
+        None => u8::MAX as u16,
+    };
+
+    let mut idents = Vec::new();
+    let mut values = Vec::new();
+    let mut next: u16 = 0;
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(variant, "BitFieldCompatible variants can not hold fields"));
+        }
+        let value = match &variant.discriminant {
+            Some((_, Expr::Lit(expr))) => match &expr.lit {
+                Lit::Int(lit) => lit.base10_parse::<u16>().map_err(|_| Error::new_spanned(lit, "discriminant does not fit in a byte"))?,
+                lit => return Err(Error::new_spanned(lit, "discriminants must be integer literals")),
+            },
+            Some((_, expr)) => return Err(Error::new_spanned(expr, "discriminants must be integer literals")),
+            None => next,
+        };
+        if value > max {
+            return Err(Error::new_spanned(variant, format!("discriminant {} does not fit in the field, values go up to {}", value, max)));
+        }
+        next = value + 1;
+        idents.push(&variant.ident);
+        values.push(Literal::u8_suffixed(value as u8));
+    }
+    let first = idents[0];
+
+    Ok(quote! {
+        impl<T> ::wasm_game_of_life::game_of_life::BitFieldCompatible<T> for #name
+        where T: ::wasm_game_of_life::game_of_life::BitFieldRepresentation {
+            fn from_type(value: T) -> Self {
+                #(
+                    if value == T::from(#values) {
+                        return #name::#idents;
+                    }
+                )*
+                #name::#first
+            }
+
+            fn to_type(&self) -> T {
+                match self {
+                    #( #name::#idents => T::from(#values), )*
+                }
+            }
+        }
+    })
+}
+
+/// Bits from `#[bitfield(bits = n)]`, between 1 and 8.
+fn bits(input: &DeriveInput) -> Result<Option<u32>, Error> {
+    let mut bits = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("bitfield")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("bits") {
+                return Err(meta.error("unknown bitfield attribute, expected `bits`"));
+            }
+            let lit: LitInt = meta.value()?.parse()?;
+            match lit.base10_parse::<u32>() {
+                Ok(value) if (1..=8).contains(&value) => {
+                    bits = Some(value);
+                    Ok(())
+                }
+                _ => Err(Error::new_spanned(lit, "bits must be between 1 and 8")),
+            }
+        })?;
+    }
+    Ok(bits)
+}
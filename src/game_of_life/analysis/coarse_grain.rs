@@ -0,0 +1,84 @@
+use crate::game_of_life::{Universe, UniverseError};
+
+impl Universe {
+
+    /// A universe with one cell for every `k`×`k` block of this one, in
+    /// `states` states, the state of a block being `reduce` of its cells.
+    ///
+    /// Blocks on the right and bottom edges are cut when the size is not a
+    /// multiple of `k`, `reduce` gets fewer cells for them. The result is a
+    /// regular universe: it can be rendered, stepped or coarse grained again.
+    pub fn coarse_grain<F>(&self, k: usize, states: usize, reduce: F) -> Result<Universe, UniverseError>
+    where F: Fn(&[u8]) -> u8 {
+        if k == 0 {
+            return Err(UniverseError::InvalidArgument("block size must be at least 1"));
+        }
+        let mut coarse = Universe::with_states(self.width().div_ceil(k), self.height().div_ceil(k), states)?;
+        coarse.set_edges(self.edges());
+        let mut block = Vec::with_capacity(k * k);
+        for by in 0..coarse.height() {
+            for bx in 0..coarse.width() {
+                block.clear();
+                for y in by * k..((by + 1) * k).min(self.height()) {
+                    for x in bx * k..((bx + 1) * k).min(self.width()) {
+                        block.push(self.get_cell(x, y)?);
+                    }
+                }
+                coarse.set_cell(bx, by, reduce(&block))?;
+            }
+        }
+        Ok(coarse)
+    }
+
+    /// Coarse grain keeping the most common state of every block, ties going
+    /// to the lowest state so half empty blocks are dead.
+    pub fn coarse_grain_majority(&self, k: usize) -> Result<Universe, UniverseError> {
+        let states = self.states();
+        self.coarse_grain(k, states, |block| {
+            let mut counts = vec![0usize; states];
+            for &cell in block {
+                counts[cell as usize] += 1;
+            }
+            let most = counts.iter().copied().max().unwrap_or(0);
+            counts.iter().position(|&count| count == most).unwrap_or(0) as u8
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_of_life::{Pattern, Universe};
+
+    #[test]
+    fn test_majority() {
+        let mut universe = Universe::new(5, 4).unwrap();
+        let art = "
+            OO.O.
+            O.OOO
+            .O.O.
+            ..O..
+        ";
+        universe.stamp(&Pattern::from_ascii_art(art, "O"), 0, 0).unwrap();
+        let coarse = universe.coarse_grain_majority(2).unwrap();
+        assert_eq!((coarse.width(), coarse.height()), (3, 2));
+        let cells: Vec<u8> = (0..6).map(|i| coarse.get_cell(i % 3, i / 3).unwrap()).collect();
+        // Top right block is (4, 0) and (4, 1): a tie.
+        assert_eq!(cells, vec![1, 1, 0, 0, 0, 0]);
+        assert!(universe.coarse_grain_majority(0).is_err());
+    }
+
+    #[test]
+    fn test_custom_reduce() {
+        let mut universe = Universe::new(4, 4).unwrap();
+        universe.set_cell(3, 3, 1).unwrap();
+        universe.set_cell(0, 0, 1).unwrap();
+        universe.set_cell(1, 1, 1).unwrap();
+        // Number of live cells of every block.
+        let density = universe.coarse_grain(2, 5, |block| block.iter().sum()).unwrap();
+        assert_eq!(density.get_cell(0, 0).unwrap(), 2);
+        assert_eq!(density.get_cell(1, 1).unwrap(), 1);
+        assert!(universe.coarse_grain(2, 2, |block| block.len() as u8).is_err());
+    }
+
+}
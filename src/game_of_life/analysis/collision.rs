@@ -0,0 +1,134 @@
+use crate::game_of_life::{Point, Rect};
+
+/// Displacement of a spaceship over its period, a glider moves by (1, 1)
+/// every 4 generations for instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Velocity {
+    pub dx: i64,
+    pub dy: i64,
+    pub period: u64,
+}
+
+impl Velocity {
+
+    pub const fn new(dx: i64, dy: i64, period: u64) -> Self {
+        Velocity { dx, dy, period }
+    }
+
+    /// Cells per generation on both axes.
+    fn per_generation(&self) -> (f64, f64) {
+        let period = self.period.max(1) as f64;
+        (self.dx as f64 / period, self.dy as f64 / period)
+    }
+
+}
+
+/// A moving object: its bounding box now and how fast it goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MovingObject {
+    pub bounds: Rect,
+    pub velocity: Velocity,
+}
+
+/// Two objects coming close enough to interact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Collision {
+    /// Indexes of the objects, `first < second`.
+    pub first: usize,
+    pub second: usize,
+    pub generation: u64,
+    /// Center of the two objects at that generation.
+    pub location: Point,
+}
+
+/// Predict which objects run into each other within `horizon` generations,
+/// soonest first.
+///
+/// Objects are assumed to keep their velocity in empty space, and to
+/// interact once their bounding boxes are less than two cells apart: a
+/// dead cell between them then sees both. Objects already interacting
+/// collide at generation 0.
+pub fn forecast_collisions(objects: &[MovingObject], horizon: u64) -> Vec<Collision> {
+    let mut collisions = Vec::new();
+    for first in 0..objects.len() {
+        for second in first + 1..objects.len() {
+            let (a, b) = (&objects[first], &objects[second]);
+            let ((avx, avy), (bvx, bvy)) = (a.velocity.per_generation(), b.velocity.per_generation());
+            let x = overlap(a.bounds.origin.x, a.bounds.size.width, avx, b.bounds.origin.x, b.bounds.size.width, bvx);
+            let y = overlap(a.bounds.origin.y, a.bounds.size.height, avy, b.bounds.origin.y, b.bounds.size.height, bvy);
+            let (start, end) = match (x, y) {
+                (Some(x), Some(y)) => (x.0.max(y.0).max(0.0), x.1.min(y.1)),
+                _ => continue,
+            };
+            // Intervals are open, the first whole generation after the start.
+            let generation = if start <= 0.0 && end > 0.0 { 0.0 } else { start.floor() + 1.0 };
+            if generation >= end || generation > horizon as f64 {
+                continue;
+            }
+            let center = |object: &MovingObject, vx: f64, vy: f64| {
+                let bounds = &object.bounds;
+                (
+                    bounds.origin.x as f64 + bounds.size.width as f64 / 2.0 + vx * generation,
+                    bounds.origin.y as f64 + bounds.size.height as f64 / 2.0 + vy * generation,
+                )
+            };
+            let ((ax, ay), (bx, by)) = (center(a, avx, avy), center(b, bvx, bvy));
+            collisions.push(Collision {
+                first,
+                second,
+                generation: generation as u64,
+                location: Point::new(((ax + bx) / 2.0).floor() as i64, ((ay + by) / 2.0).floor() as i64),
+            });
+        }
+    }
+    collisions.sort_by_key(|collision| (collision.generation, collision.first, collision.second));
+    collisions
+}
+
+/// Generations when two segments moving on one axis are less than two cells
+/// apart, as an open interval.
+fn overlap(a: i64, a_length: usize, a_speed: f64, b: i64, b_length: usize, b_speed: f64) -> Option<(f64, f64)> {
+    // How far b starts from a over time, it has to stay in (low, high).
+    let (offset, speed) = ((b - a) as f64, b_speed - a_speed);
+    let (low, high) = (-(b_length as f64) - 2.0, a_length as f64 + 2.0);
+    if speed == 0.0 {
+        return if offset > low && offset < high { Some((f64::NEG_INFINITY, f64::INFINITY)) } else { None };
+    }
+    let (enter, leave) = ((low - offset) / speed, (high - offset) / speed);
+    Some((enter.min(leave), enter.max(leave)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{forecast_collisions, MovingObject, Velocity};
+    use crate::game_of_life::{Point, Rect, Size};
+
+    #[test]
+    fn test_head_on_gliders() {
+        let glider = |x, y, dx, dy| MovingObject {
+            bounds: Rect::new(Point::new(x, y), Size::new(3, 3)),
+            velocity: Velocity::new(dx, dy, 4),
+        };
+        // 20 cells apart on both axes, closing in at c/2.
+        let objects = [glider(0, 0, 1, 1), glider(20, 20, -1, -1), glider(0, 40, -1, 0)];
+        let collisions = forecast_collisions(&objects, 1000);
+        assert_eq!(collisions.len(), 1);
+        let collision = collisions[0];
+        assert_eq!((collision.first, collision.second), (0, 1));
+        // The gap of 17 cells has to shrink below 2.
+        assert_eq!(collision.generation, 31);
+        assert_eq!(collision.location, Point::new(11, 11));
+        assert!(forecast_collisions(&objects, 30).is_empty());
+    }
+
+    #[test]
+    fn test_static_objects() {
+        let block = |x| MovingObject {
+            bounds: Rect::new(Point::new(x, 0), Size::new(2, 2)),
+            velocity: Velocity::new(0, 0, 1),
+        };
+        assert_eq!(forecast_collisions(&[block(0), block(3)], 10)[0].generation, 0);
+        assert!(forecast_collisions(&[block(0), block(4)], 10).is_empty());
+    }
+
+}
@@ -0,0 +1,156 @@
+use crate::game_of_life::rules::Rule;
+use crate::game_of_life::{Universe, UniverseError};
+
+/// Windows the population history is averaged over, smoothing out
+/// oscillations shorter than a window.
+const WINDOWS: usize = 8;
+
+/// How the population of a pattern grows in the long run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowthClass {
+    /// Everything died.
+    Extinct,
+    /// Still lifes, oscillators and spaceships.
+    Bounded,
+    /// Guns and puffers, adding the same amount of cells every period.
+    Linear,
+    /// Breeders, whose guns multiply.
+    Quadratic,
+}
+
+/// The classification and the fits it is based on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrowthReport {
+    pub class: GrowthClass,
+    /// Least squares line `intercept + slope * generation`.
+    pub intercept: f64,
+    pub slope: f64,
+    /// `t²` coefficient of the least squares parabola.
+    pub quadratic: f64,
+}
+
+/// Run `universe` for `generations` generations of `rule` and classify how
+/// its population grows.
+///
+/// The universe has to be large enough for the pattern to grow freely,
+/// what is lost against its edges is not counted.
+pub fn classify_growth(universe: &mut Universe, rule: &dyn Rule, generations: u64) -> Result<GrowthReport, UniverseError> {
+    let mut populations = vec![universe.population()];
+    for _ in 0..generations {
+        universe.step(rule)?;
+        populations.push(universe.population());
+    }
+    fit_growth(&populations)
+}
+
+/// Classify a population history, one sample per generation.
+///
+/// This is a heuristic: the history is averaged over windows and the
+/// pattern is bounded if the average barely moves. Otherwise it grows
+/// quadratically when the `t²` term of the parabola fit makes at least half
+/// of the growth over the history, linearly when it does not.
+pub fn fit_growth(populations: &[usize]) -> Result<GrowthReport, UniverseError> {
+    if populations.len() < 2 * WINDOWS {
+        return Err(UniverseError::InvalidArgument("need at least 16 generations of history"));
+    }
+    let samples: Vec<(f64, f64)> = populations.iter().enumerate().map(|(t, &p)| (t as f64, p as f64)).collect();
+    let (intercept, slope) = fit_line(&samples);
+    let quadratic = fit_parabola(&samples);
+
+    let window = populations.len() / WINDOWS;
+    let means: Vec<f64> = populations
+        .chunks_exact(window)
+        .take(WINDOWS)
+        .map(|chunk| chunk.iter().sum::<usize>() as f64 / window as f64)
+        .collect();
+    let (first, last) = (means[0], means[WINDOWS - 1]);
+    let span = (populations.len() - 1) as f64;
+    let class = if populations.last() == Some(&0) {
+        GrowthClass::Extinct
+    } else if last - first <= (0.1 * first).max(1.0) {
+        GrowthClass::Bounded
+    } else if quadratic * span * span >= 0.5 * (last - first) {
+        GrowthClass::Quadratic
+    } else {
+        GrowthClass::Linear
+    };
+    Ok(GrowthReport { class, intercept, slope, quadratic })
+}
+
+fn fit_line(samples: &[(f64, f64)]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let (sum_t, sum_p) = samples.iter().fold((0.0, 0.0), |(t, p), &(st, sp)| (t + st, p + sp));
+    let (mean_t, mean_p) = (sum_t / n, sum_p / n);
+    let (covariance, variance) = samples.iter().fold((0.0, 0.0), |(c, v), &(t, p)| {
+        (c + (t - mean_t) * (p - mean_p), v + (t - mean_t) * (t - mean_t))
+    });
+    let slope = if variance == 0.0 { 0.0 } else { covariance / variance };
+    (mean_p - slope * mean_t, slope)
+}
+
+/// `t²` coefficient of the least squares parabola, from the normal equations
+/// solved with Cramer's rule.
+fn fit_parabola(samples: &[(f64, f64)]) -> f64 {
+    let mut s = [0.0; 5];
+    let mut r = [0.0; 3];
+    for &(t, p) in samples {
+        let mut power = 1.0;
+        for (k, sum) in s.iter_mut().enumerate() {
+            *sum += power;
+            if k < 3 {
+                r[k] += power * p;
+            }
+            power *= t;
+        }
+    }
+    let determinant = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let matrix = [[s[0], s[1], s[2]], [s[1], s[2], s[3]], [s[2], s[3], s[4]]];
+    let mut replaced = matrix;
+    for (row, value) in replaced.iter_mut().zip(r.iter()) {
+        row[2] = *value;
+    }
+    let d = determinant(matrix);
+    if d == 0.0 { 0.0 } else { determinant(replaced) / d }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{classify_growth, fit_growth, GrowthClass};
+    use crate::game_of_life::rules;
+    use crate::game_of_life::{PatternCatalog, Universe};
+
+    #[test]
+    fn test_fits() {
+        let quadratic: Vec<usize> = (0..100).map(|t| 10 + t * t / 4).collect();
+        let report = fit_growth(&quadratic).unwrap();
+        assert_eq!(report.class, GrowthClass::Quadratic);
+        assert!((report.quadratic - 0.25).abs() < 0.01);
+
+        let linear: Vec<usize> = (0..100).map(|t| 20 + 5 * (t / 30) + t % 3).collect();
+        let report = fit_growth(&linear).unwrap();
+        assert_eq!(report.class, GrowthClass::Linear);
+        assert!((report.slope - 1.0 / 6.0).abs() < 0.05);
+
+        assert_eq!(fit_growth(&[12, 14, 13, 12].repeat(10)).unwrap().class, GrowthClass::Bounded);
+        assert_eq!(fit_growth(&[3, 2, 1, 0].repeat(10)).unwrap().class, GrowthClass::Extinct);
+        assert!(fit_growth(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_gun_and_blinker() {
+        let life = rules::parse("B3/S23").unwrap();
+        let catalog = PatternCatalog::builtin();
+        let run = |name: &str, generations| {
+            let entry = catalog.entries().iter().find(|entry| entry.name() == name).unwrap();
+            let mut universe = Universe::new(64, 64).unwrap();
+            universe.stamp(&entry.pattern, 1, 1).unwrap();
+            classify_growth(&mut universe, life.as_ref(), generations).unwrap().class
+        };
+        assert_eq!(run("Blinker", 40), GrowthClass::Bounded);
+        assert_eq!(run("Gosper glider gun", 150), GrowthClass::Linear);
+    }
+
+}
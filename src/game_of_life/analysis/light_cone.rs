@@ -0,0 +1,109 @@
+use crate::game_of_life::rules::Neighborhood;
+use crate::game_of_life::render::check_rgba_buffer;
+use crate::game_of_life::{Color, EdgeBehavior, Point, Rect, Size, Universe, UniverseError};
+
+/// Cells that can influence a cell within some generations, or that it can
+/// influence.
+///
+/// Information travels at most one neighborhood step a generation, so the
+/// cells at generation `g - n` that the cell at generation `g` depends on
+/// are the ones `n` steps away. Neighborhoods are symmetric: the same cells
+/// are the forward cone, the ones the cell can affect by `g + n`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LightCone {
+    pub apex: Point,
+    pub generations: u64,
+    pub neighborhood: Neighborhood,
+}
+
+/// The light cone of `cell` over `generations` generations.
+pub fn light_cone(cell: Point, generations: u64, neighborhood: Neighborhood) -> LightCone {
+    LightCone { apex: cell, generations, neighborhood }
+}
+
+impl LightCone {
+
+    /// Whether information can travel between the apex and `point`.
+    pub fn contains(&self, point: Point) -> bool {
+        self.distance((point.x - self.apex.x).unsigned_abs(), (point.y - self.apex.y).unsigned_abs())
+    }
+
+    fn distance(&self, dx: u64, dy: u64) -> bool {
+        match self.neighborhood {
+            Neighborhood::Moore => dx.max(dy) <= self.generations,
+            Neighborhood::VonNeumann => dx.saturating_add(dy) <= self.generations,
+        }
+    }
+
+    /// The smallest rectangle holding the cone.
+    pub fn bounds(&self) -> Rect {
+        let radius = self.generations.min(i64::MAX as u64 / 2) as i64;
+        let side = (2 * radius + 1) as usize;
+        Rect::new(Point::new(self.apex.x - radius, self.apex.y - radius), Size::new(side, side))
+    }
+
+    /// Whether grid cell (x, y) of the universe is in the cone, the cone
+    /// going around the edges when they wrap.
+    pub fn contains_cell(&self, universe: &Universe, x: usize, y: usize) -> bool {
+        let (dx, dy) = ((x as i64 - self.apex.x).unsigned_abs(), (y as i64 - self.apex.y).unsigned_abs());
+        match universe.edges() {
+            EdgeBehavior::Wrap => {
+                let (width, height) = (universe.width() as u64, universe.height() as u64);
+                self.distance(dx.min(width.saturating_sub(dx)), dy.min(height.saturating_sub(dy)))
+            }
+            _ => self.distance(dx, dy),
+        }
+    }
+
+    /// Tint the cone on an RGBA rendering of the universe, mixing `color`
+    /// half and half with the cells.
+    pub fn overlay_rgba(&self, universe: &Universe, buffer: &mut [u8], color: Color) -> Result<(), UniverseError> {
+        check_rgba_buffer(universe, buffer)?;
+        for y in 0..universe.height() {
+            for x in 0..universe.width() {
+                if self.contains_cell(universe, x, y) {
+                    let offset = (y * universe.width() + x) * 4;
+                    let pixel = Color::new(buffer[offset], buffer[offset + 1], buffer[offset + 2]).lerp(color, 0.5);
+                    buffer[offset..offset + 3].copy_from_slice(&[pixel.r, pixel.g, pixel.b]);
+                }
+            }
+        }
+        Ok(())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::light_cone;
+    use crate::game_of_life::rules::Neighborhood;
+    use crate::game_of_life::{Color, EdgeBehavior, Point, Rect, Size, Universe};
+
+    #[test]
+    fn test_cone_shapes() {
+        let moore = light_cone(Point::new(5, 5), 2, Neighborhood::Moore);
+        assert_eq!(moore.bounds(), Rect::new(Point::new(3, 3), Size::new(5, 5)));
+        assert!(moore.contains(Point::new(7, 3)));
+        assert!(!moore.contains(Point::new(8, 5)));
+
+        let von_neumann = light_cone(Point::new(5, 5), 2, Neighborhood::VonNeumann);
+        assert!(von_neumann.contains(Point::new(6, 6)));
+        assert!(!von_neumann.contains(Point::new(7, 3)));
+        assert_eq!(von_neumann.bounds().points().filter(|&point| von_neumann.contains(point)).count(), 13);
+    }
+
+    #[test]
+    fn test_wrapped_overlay() {
+        let mut universe = Universe::new(8, 8).unwrap();
+        universe.set_edges(EdgeBehavior::Wrap);
+        let cone = light_cone(Point::new(0, 0), 1, Neighborhood::Moore);
+        assert!(cone.contains_cell(&universe, 7, 7));
+
+        let mut buffer = vec![0; 8 * 8 * 4];
+        cone.overlay_rgba(&universe, &mut buffer, Color::new(200, 100, 0)).unwrap();
+        assert_eq!(&buffer[..4], &[100, 50, 0, 0]);
+        assert_eq!(&buffer[8..12], &[0, 0, 0, 0]);
+        assert_eq!(buffer.chunks(4).filter(|pixel| pixel[0] == 100).count(), 9);
+    }
+
+}
@@ -0,0 +1,10 @@
+//! Analysis of patterns and their evolution.
+
+pub mod coarse_grain;
+pub mod collision;
+pub mod growth;
+pub mod light_cone;
+
+pub use collision::{forecast_collisions, Collision, MovingObject, Velocity};
+pub use growth::{classify_growth, fit_growth, GrowthClass, GrowthReport};
+pub use light_cone::{light_cone, LightCone};
@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use super::{BitFieldCompatible, BitFieldRepresentation, BitsField, BitsFieldError};
+
+/// An atomic integer a `AtomicBitsField` can be backed by.
+pub trait AtomicWord: Sized {
+    type Value: BitFieldRepresentation;
+
+    fn new(value: Self::Value) -> Self;
+    fn load(&self, order: Ordering) -> Self::Value;
+    /// Replace the value by `f(value)`, retrying until no other thread
+    /// wrote in between.
+    fn update<F>(&self, f: F)
+    where F: FnMut(Self::Value) -> Self::Value;
+}
+
+macro_rules! impl_atomic_word {
+    ($($atomic:ty => $t:ty),*) => {$(
+        impl AtomicWord for $atomic {
+            type Value = $t;
+
+            fn new(value: $t) -> Self {
+                <$atomic>::new(value)
+            }
+
+            fn load(&self, order: Ordering) -> $t {
+                <$atomic>::load(self, order)
+            }
+
+            fn update<F>(&self, mut f: F)
+            where F: FnMut($t) -> $t {
+                // The closure always returns a value, so this never fails.
+                let _ = self.fetch_update(Ordering::AcqRel, Ordering::Acquire, |value| Some(f(value)));
+            }
+        }
+    )*};
+}
+
+impl_atomic_word!(AtomicU32 => u32, AtomicU64 => u64);
+
+/// A `BitsField` whose values can be set from several threads at once.
+///
+/// Setting a value is a compare-and-swap of its word, so writers of
+/// different values of a same word never lose each other's writes. Values
+/// can not straddle words, `bits_per_element` has to divide the bits of a
+/// word.
+pub struct AtomicBitsField<A>
+where A: AtomicWord {
+    bits_per_element: usize,
+    elements: Vec<A>,
+}
+
+impl<A> AtomicBitsField<A>
+where A: AtomicWord {
+
+    pub fn new(bits_per_element: usize, size: usize) -> Result<Self, BitsFieldError> {
+        Self::from_field(BitsField::new(bits_per_element, size)?)
+    }
+
+    /// Share the values of a field between threads.
+    pub fn from_field(field: BitsField<A::Value>) -> Result<Self, BitsFieldError> {
+        let bits_per_element = field.bits_per_element();
+        // Values straddling words could not be set with a single swap.
+        if !Self::element_size().is_multiple_of(bits_per_element) {
+            return Err(BitsFieldError::InvalidBitsPerElement { bits: bits_per_element, word_bits: Self::element_size() });
+        }
+        Ok(AtomicBitsField { bits_per_element, elements: field.into_vec().into_iter().map(A::new).collect() })
+    }
+
+    /// Back to a field, once every writer is done.
+    pub fn into_field(self) -> BitsField<A::Value> {
+        let words: Vec<A::Value> = self.elements.iter().map(|word| word.load(Ordering::Acquire)).collect();
+        // The length was valid for the field the words came from.
+        BitsField::from_raw_parts(self.bits_per_element, &words).unwrap()
+    }
+
+    pub fn bits_per_element(&self) -> usize {
+        self.bits_per_element
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len() * (Self::element_size() / self.bits_per_element)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn get<Output>(&self, index: usize) -> Result<Output, BitsFieldError>
+    where Output: BitFieldCompatible<A::Value> {
+        let (word, shift, mask) = self.locate(index)?;
+        let value = (self.elements[word].load(Ordering::Acquire) >> shift) & mask;
+        Ok(Output::from_type(value))
+    }
+
+    pub fn set<Output>(&self, index: usize, value: Output) -> Result<(), BitsFieldError>
+    where Output: BitFieldCompatible<A::Value> {
+        let (word, shift, mask) = self.locate(index)?;
+        let value = (value.to_type() & mask) << shift;
+        self.elements[word].update(|current| (current & !(mask << shift)) | value);
+        Ok(())
+    }
+
+    fn element_size() -> usize {
+        std::mem::size_of::<A::Value>() * 8
+    }
+
+    /// Word, shift and unshifted mask of the value at `index`.
+    fn locate(&self, index: usize) -> Result<(usize, u8, A::Value), BitsFieldError> {
+        if index >= self.len() {
+            return Err(BitsFieldError::OutOfBounds { index, len: self.len() });
+        }
+        let per_word = Self::element_size() / self.bits_per_element;
+        let mask = if self.bits_per_element == Self::element_size() {
+            !A::Value::from(0)
+        } else {
+            !(!A::Value::from(0) << self.bits_per_element as u8)
+        };
+        Ok((index / per_word, ((index % per_word) * self.bits_per_element) as u8, mask))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+    use std::thread;
+
+    use super::AtomicBitsField;
+    use crate::game_of_life::BitsFieldError;
+
+    #[test]
+    fn test_concurrent_sets() {
+        // Every thread writes one value out of four, all in the same words.
+        let field = AtomicBitsField::<AtomicU32>::new(2, 400).unwrap();
+        thread::scope(|scope| {
+            for thread in 0..4 {
+                let field = &field;
+                scope.spawn(move || {
+                    for index in (thread..400).step_by(4) {
+                        field.set(index, thread as u8).unwrap();
+                    }
+                });
+            }
+        });
+        assert!((0..400).all(|index| field.get::<u8>(index).unwrap() == (index % 4) as u8));
+
+        let field = field.into_field();
+        assert_eq!(field.get::<u8>(7).unwrap(), 3);
+        assert_eq!(AtomicBitsField::<AtomicU32>::new(3, 4).err(), Some(BitsFieldError::InvalidBitsPerElement { bits: 3, word_bits: 32 }));
+        assert_eq!(AtomicBitsField::<AtomicU32>::new(8, 16).unwrap().get::<u8>(16).err(), Some(BitsFieldError::OutOfBounds { index: 16, len: 16 }));
+    }
+
+}
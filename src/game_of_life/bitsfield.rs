@@ -1,14 +1,32 @@
-use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not, Range, Shl, Shr};
 use std::mem::size_of;
 
-pub trait BitFieldRepresentation: BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self> + Shl<u8, Output = Self> + Shr<u8, Output = Self> + Copy + Sized + From<u8>
-{}
+use super::BitsFieldError;
 
-impl BitFieldRepresentation for u8 {}
-impl BitFieldRepresentation for u16 {}
-impl BitFieldRepresentation for u32 {}
-impl BitFieldRepresentation for u64 {}
-impl BitFieldRepresentation for u128 {}
+pub trait BitFieldRepresentation: BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self> + Not<Output = Self> + Shl<u8, Output = Self> + Shr<u8, Output = Self> + Copy + Sized + From<u8> + PartialEq
+{
+    fn count_ones(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+}
+
+macro_rules! impl_representation {
+    ($($t:ty),*) => {$(
+        impl BitFieldRepresentation for $t {
+            fn count_ones(self) -> u32 {
+                <$t>::count_ones(self)
+            }
+
+            fn trailing_zeros(self) -> u32 {
+                <$t>::trailing_zeros(self)
+            }
+        }
+    )*};
+}
+
+impl_representation!(u8, u16, u32, u64, u128);
 
 pub trait BitFieldCompatible<T>
 where T: BitFieldRepresentation {
@@ -16,138 +34,994 @@ where T: BitFieldRepresentation {
     fn to_type(&self) -> T;
 }
 
+/// Raw values are stored as is, reading a field of more than 8 bits per
+/// element as `u8` keeps the lowest byte.
+macro_rules! impl_raw_u8 {
+    ($($t:ty),*) => {$(
+        impl BitFieldCompatible<$t> for u8 {
+            fn from_type(value: $t) -> Self {
+                value as u8
+            }
+
+            fn to_type(&self) -> $t {
+                <$t>::from(*self)
+            }
+        }
+    )*};
+}
+
+impl_raw_u8!(u8, u16, u32, u64, u128);
+
+/// Words are stored as is, for values wider than a byte.
+macro_rules! impl_raw_word {
+    ($($t:ty),*) => {$(
+        impl BitFieldCompatible<$t> for $t {
+            fn from_type(value: $t) -> Self {
+                value
+            }
+
+            fn to_type(&self) -> $t {
+                *self
+            }
+        }
+    )*};
+}
+
+impl_raw_word!(u16, u32, u64, u128);
+
+impl<T> BitFieldCompatible<T> for bool
+where T: BitFieldRepresentation {
+    fn from_type(value: T) -> Self {
+        value != T::from(0)
+    }
+
+    fn to_type(&self) -> T {
+        T::from(*self as u8)
+    }
+}
+
+/// Where the values of a field are in its words: value `i` starts at bit
+/// `i * bits_per_element` of the words taken as one string of bits, word 0
+/// first and each word from its lowest bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitLayout {
+    pub bits_per_element: usize,
+    pub word_bits: usize,
+    /// Values in each word, `None` when values straddle words.
+    pub values_per_word: Option<usize>,
+    /// Values in the field, padding values of the last word included.
+    pub len: usize,
+}
+
 /// A BitsField is a field of bits.
 /// Each element is represented by a certain number of bits.
+#[derive(Clone)]
 pub struct BitsField<T>
 where T: BitFieldRepresentation  {
     bits_per_element: usize,
     element_size: usize,
+    /// The `bits_per_element` lowest bits.
+    mask: T,
+    /// Values per word when values never straddle two words, 0 otherwise.
+    per_element: usize,
     elements: Vec<T>,
 }
 
 impl<T> BitsField<T>
 where T: BitFieldRepresentation {
 
-    pub fn default(bits_per_element: usize) -> Self {
-        BitsField {
-            bits_per_element: 1,
-            element_size: size_of::<T>()*8,
-            elements: Vec::new(),
+    /// An empty field with room for `size` values of `bits_per_element`
+    /// bits, see `new` for the widths allowed.
+    pub fn with_capacity(bits_per_element: usize, size: usize) -> Result<Self, BitsFieldError> {
+        let mut field = BitsField::new(bits_per_element, 0)?;
+        field.reserve(size)?;
+        Ok(field)
+    }
+
+    /// A field of `size` values of `bits_per_element` bits, at most the
+    /// bits of a word. Values wider than a byte need a `BitFieldCompatible`
+    /// type as wide, like `T` itself.
+    pub fn new(bits_per_element: usize, size: usize) -> Result<Self, BitsFieldError> {
+        let element_size = size_of::<T>()*8;
+        if bits_per_element == 0 {
+            return Err(BitsFieldError::InvalidBitsPerElement { bits: bits_per_element, word_bits: element_size });
+        }
+        if bits_per_element > element_size {
+            return Err(BitsFieldError::InvalidBitsPerElement { bits: bits_per_element, word_bits: element_size });
         }
+        let count = elements_for(size, bits_per_element, element_size).ok_or(BitsFieldError::SizeOverflow)?;
+        let mut elements = Vec::new();
+        elements.try_reserve_exact(count).map_err(|_| BitsFieldError::OutOfMemory)?;
+        elements.resize(count, T::from(0));
+        Ok(BitsField {
+            bits_per_element,
+            element_size,
+            mask: low_mask(bits_per_element, element_size),
+            per_element: if element_size.is_multiple_of(bits_per_element) { element_size / bits_per_element } else { 0 },
+            elements,
+        })
+    }
+
+    /// A field over words saved with `as_slice`, without decoding them.
+    ///
+    /// Bits of the last word past the last value are cleared, so stray
+    /// bits there never show in `count_ones` or comparisons.
+    pub fn from_raw_parts(bits_per_element: usize, elements: &[T]) -> Result<Self, BitsFieldError> {
+        let mut field = BitsField::new(bits_per_element, 0)?;
+        let element_size = field.element_size;
+        // The length must be addressable, like when built by `new`.
+        let len = usize::try_from(elements.len() as u128 * element_size as u128 / bits_per_element as u128).map_err(|_| BitsFieldError::SizeOverflow)?;
+        field.elements = elements.to_vec();
+        field.resize(len)?;
+        Ok(field)
+    }
+
+    /// The backing words, values packed from their lowest bit.
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    /// The backing words, for engines processing a whole word of values at
+    /// a time. See `layout` for where values are.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.elements
     }
 
-    pub fn new(bits_per_element: usize, size: usize) -> Result<Self, &'static str> {
-        match bits_per_element {
-            0 => Err("bits_per_element must be greater than 0"),
-            1..=8 => Ok(BitsField {
-                bits_per_element,
-                element_size: size_of::<T>()*8,
-                elements: vec![T::from(0); (size as f64 * bits_per_element as f64 / (size_of::<T>()*8) as f64).ceil() as usize],
-            }),
-            _ => Err("bits_per_element must be less than 8"),
+    /// How values are packed in the backing words.
+    pub fn layout(&self) -> BitLayout {
+        BitLayout {
+            bits_per_element: self.bits_per_element,
+            word_bits: self.element_size,
+            values_per_word: if self.per_element == 0 { None } else { Some(self.per_element) },
+            len: self.len(),
         }
     }
 
+    pub fn into_vec(self) -> Vec<T> {
+        self.elements
+    }
+
+    pub fn bits_per_element(&self) -> usize {
+        self.bits_per_element
+    }
+
     pub fn clear(&mut self) {
         self.elements = Vec::new();
     }
 
+    /// Grow or shrink the field to hold at least `new_len` values, keeping
+    /// the packed words as they are. New values are 0.
+    pub fn resize(&mut self, new_len: usize) -> Result<(), BitsFieldError> {
+        let count = elements_for(new_len, self.bits_per_element, self.element_size).ok_or(BitsFieldError::SizeOverflow)?;
+        if count > self.elements.len() {
+            self.reserve(new_len - self.len().min(new_len))?;
+        }
+        self.elements.resize(count, T::from(0));
+        // Values left in the last word past the new length are cleared, so
+        // growing again gives zeros.
+        let (bits, size) = (self.bits_per_element, self.element_size);
+        let kept = new_len as u128 * bits as u128;
+        let end = self.elements.len() as u128 * size as u128;
+        if kept < end {
+            // Less than a word is left.
+            write_bits(&mut self.elements, size, kept, (end - kept) as usize, T::from(0));
+        }
+        Ok(())
+    }
+
+    /// Make room for `additional` more values without reallocating.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), BitsFieldError> {
+        let wanted = self.len().checked_add(additional).ok_or(BitsFieldError::SizeOverflow)?;
+        let count = elements_for(wanted, self.bits_per_element, self.element_size).ok_or(BitsFieldError::SizeOverflow)?;
+        self.elements
+            .try_reserve_exact(count.saturating_sub(self.elements.len()))
+            .map_err(|_| BitsFieldError::OutOfMemory)
+    }
+
     pub fn len(&self) -> usize {
-        ((self.elements.len() * self.element_size) as f64 / self.bits_per_element as f64).floor() as usize
+        // `new` made sure this fits in an usize. Every bounds check asks,
+        // so 128 bits divisions are left to values straddling words.
+        if self.per_element != 0 {
+            self.elements.len() * self.per_element
+        } else {
+            (self.elements.len() as u128 * self.element_size as u128 / self.bits_per_element as u128) as usize
+        }
     }
 
     pub fn is_empty(&self) -> bool {
         self.elements.is_empty()
     }
 
-    fn convert_index(&self, index: usize) -> Result<(usize, usize), &'static str> {
+    /// The backing words, values packed from their lowest bit.
+    pub fn as_ptr(&self) -> *const T {
+        self.elements.as_ptr()
+    }
+
+    fn convert_index(&self, index: usize) -> Result<(usize, usize), BitsFieldError> {
         if index >= self.len() {
-            Err("index out of bounds")
+            Err(BitsFieldError::OutOfBounds { index, len: self.len() })
         } else {
-            let element_index = (index * self.bits_per_element as usize) / self.element_size;
-            let bit_index = (index * self.bits_per_element as usize) % self.element_size;
-            Ok((element_index, bit_index))
+            Ok(locate(index, self.bits_per_element, self.element_size))
         }
     }
 
-    pub fn get<Output>(&self, index: usize) -> Result<Output, &str> 
+    pub fn get<Output>(&self, index: usize) -> Result<Output, BitsFieldError>
     where Output: BitFieldCompatible<T> {
-        let (element_index, bit_index) = self.convert_index(index)?;
-        // Maybe the data is overlapping on the next element.
-        Ok(Output::from_type(
-            if (self.element_size - bit_index) < self.bits_per_element {
-                // We need to get some bits from the next element.
-                // There is two masks to build.
-                // The mask of the current element.
-                // The mask of the next element.
-
-                let left_index = self.element_size - bit_index;
-                let right_index = self.bits_per_element - left_index;
-
-                let mut current_mask = T::from(0);
-                for i in 0..left_index {
-                    current_mask = current_mask | (T::from(1) << (bit_index + i) as u8)
-                }
-                let mut next_mask = T::from(0);
-                for i in 0..right_index {
-                    next_mask = next_mask | (T::from(1) << i as u8)
-                }
-                ((self.elements[element_index] & current_mask) << right_index as u8) | ((self.elements[element_index + 1] & next_mask) >> (self.element_size - right_index) as u8)
-            } else {
-                // We can get all the bits from the current element.
-                // We need to mask the bits we want.
-                // Let's build the mask.
-                let mut mask = T::from(0);
-                for i in 0..self.bits_per_element {
-                    mask = mask | (T::from(1) << (bit_index + i) as u8)
-                }
-                self.elements[element_index] & mask
+        let bit = self.bit_offset(index)?;
+        // Values may straddle two words.
+        Ok(Output::from_type(read_bits(&self.elements, self.element_size, bit, self.bits_per_element)))
+    }
+
+    pub fn set<Output>(&mut self, index: usize, value: Output) -> Result<(), BitsFieldError>
+    where Output: BitFieldCompatible<T> {
+        let bit = self.bit_offset(index)?;
+        write_bits(&mut self.elements, self.element_size, bit, self.bits_per_element, value.to_type());
+        Ok(())
+    }
+
+    /// Same as `get`, without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than `len()`, which is only checked by debug
+    /// builds.
+    #[inline]
+    pub unsafe fn get_unchecked<Output>(&self, index: usize) -> Output
+    where Output: BitFieldCompatible<T> {
+        debug_assert!(index < self.len(), "index out of bounds");
+        if self.per_element == 0 {
+            return Output::from_type(self.read(index));
+        }
+        let (word, slot) = (index / self.per_element, index % self.per_element);
+        let element = *self.elements.get_unchecked(word);
+        Output::from_type((element >> (slot * self.bits_per_element) as u8) & self.mask)
+    }
+
+    /// Same as `set`, without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than `len()`, which is only checked by debug
+    /// builds.
+    #[inline]
+    pub unsafe fn set_unchecked<Output>(&mut self, index: usize, value: Output)
+    where Output: BitFieldCompatible<T> {
+        debug_assert!(index < self.len(), "index out of bounds");
+        if self.per_element == 0 {
+            write_bits(&mut self.elements, self.element_size, index as u128 * self.bits_per_element as u128, self.bits_per_element, value.to_type());
+            return;
+        }
+        let (word, slot) = (index / self.per_element, index % self.per_element);
+        let shift = (slot * self.bits_per_element) as u8;
+        let element = self.elements.get_unchecked_mut(word);
+        *element = (*element & !(self.mask << shift)) | ((value.to_type() & self.mask) << shift);
+    }
+
+    /// Every value in order, decoded as `Output`.
+    pub fn iter<Output>(&self) -> impl Iterator<Item = Output> + '_
+    where Output: BitFieldCompatible<T> {
+        (0..self.len()).map(move |index| Output::from_type(self.read(index)))
+    }
+
+    /// Every value decoded as `Output`, to be indexed with `view[i]`.
+    pub fn view<Output>(&self) -> BitsFieldView<Output>
+    where Output: BitFieldCompatible<T> {
+        BitsFieldView { values: self.iter().collect() }
+    }
+
+    /// Values `y * stride` to `(y + 1) * stride` of a field holding rows of
+    /// `stride` values, bounds checked once for the whole row.
+    pub fn row(&self, y: usize, stride: usize) -> Result<RowView<'_, T>, BitsFieldError> {
+        let start = self.row_start(y, stride)?;
+        Ok(RowView { field: self, start, len: stride })
+    }
+
+    /// Same as `row`, with the values settable.
+    pub fn row_mut(&mut self, y: usize, stride: usize) -> Result<RowViewMut<'_, T>, BitsFieldError> {
+        let start = self.row_start(y, stride)?;
+        Ok(RowViewMut { field: self, start, len: stride })
+    }
+
+    fn row_start(&self, y: usize, stride: usize) -> Result<usize, BitsFieldError> {
+        y.checked_mul(stride)
+            .filter(|start| start.checked_add(stride).is_some_and(|end| end <= self.len()))
+            .ok_or(BitsFieldError::OutOfBounds { index: y.saturating_mul(stride), len: self.len() })
+    }
+
+    /// Number of set bits, which is the number of `true` values of a one
+    /// bit field.
+    pub fn count_ones(&self) -> usize {
+        self.elements.iter().map(|&element| element.count_ones() as usize).sum()
+    }
+
+    /// Number of values equal to `value`, padding values at the end of the
+    /// last word included.
+    ///
+    /// When values do not straddle words, every word is compared at once.
+    pub fn count_matching<Output>(&self, value: Output) -> usize
+    where Output: BitFieldCompatible<T> {
+        let (bits, size) = (self.bits_per_element, self.element_size);
+        let value = value.to_type() & low_mask(bits, size);
+        if size % bits != 0 {
+            return (0..self.len()).filter(|&index| self.read(index) == value).count();
+        }
+        let (repeated, lowest) = repeat(value, bits, size);
+        let per_element = size / bits;
+        self.elements
+            .iter()
+            .map(|&element| per_element - differing(element, repeated, lowest, bits).count_ones() as usize)
+            .sum()
+    }
+
+    /// Index of the first value equal to `value` from `from` on, padding
+    /// values at the end of the last word included like `count_matching`.
+    ///
+    /// When values do not straddle words, whole words of other values are
+    /// skipped at once and the match found from trailing zeros.
+    pub fn find_next<Output>(&self, from: usize, value: Output) -> Option<usize>
+    where Output: BitFieldCompatible<T> {
+        self.find(from, value.to_type(), true)
+    }
+
+    /// Index of the first value other than `value` from `from` on, like
+    /// the next live cell of a field of mostly dead ones.
+    pub fn find_next_not<Output>(&self, from: usize, value: Output) -> Option<usize>
+    where Output: BitFieldCompatible<T> {
+        self.find(from, value.to_type(), false)
+    }
+
+    fn find(&self, from: usize, value: T, equal: bool) -> Option<usize> {
+        let (bits, size) = (self.bits_per_element, self.element_size);
+        let value = value & low_mask(bits, size);
+        if size % bits != 0 {
+            return (from..self.len()).find(|&index| (self.read(index) == value) == equal);
+        }
+        let per_element = size / bits;
+        let (repeated, lowest) = repeat(value, bits, size);
+        let first = from / per_element;
+        for (word, &element) in self.elements.iter().enumerate().skip(first) {
+            let mut found = differing(element, repeated, lowest, bits);
+            if equal {
+                found = !found & lowest;
             }
-        ))
+            if word == first {
+                // Skip the values before `from` in its word.
+                found = found & (!T::from(0) << ((from % per_element) * bits) as u8);
+            }
+            if found != T::from(0) {
+                return Some(word * per_element + found.trailing_zeros() as usize / bits);
+            }
+        }
+        None
+    }
+
+    /// Replace every value by `f(index, value)`.
+    pub fn update<Output, F>(&mut self, mut f: F)
+    where Output: BitFieldCompatible<T>, F: FnMut(usize, Output) -> Output {
+        for index in 0..self.len() {
+            let value = f(index, Output::from_type(self.read(index)));
+            write_bits(&mut self.elements, self.element_size, index as u128 * self.bits_per_element as u128, self.bits_per_element, value.to_type());
+        }
     }
 
-    pub fn set<Output>(&mut self, index: usize, value: Output) -> Result<(), &'static str>
+    /// Value at an index known to be in bounds.
+    fn read(&self, index: usize) -> T {
+        read_bits(&self.elements, self.element_size, index as u128 * self.bits_per_element as u128, self.bits_per_element)
+    }
+
+    /// The bitwise difference of two fields of the same shape: values are
+    /// 0 where both fields agree, computed a word at a time.
+    pub fn diff(&self, other: &BitsField<T>) -> Result<BitsField<T>, BitsFieldError> {
+        let mut diff = self.clone();
+        diff.combine(other, |a, b| a ^ b)?;
+        Ok(diff)
+    }
+
+    /// Replace every word by `f(word, other word)`.
+    fn combine<F>(&mut self, other: &BitsField<T>, f: F) -> Result<(), BitsFieldError>
+    where F: Fn(T, T) -> T {
+        if self.bits_per_element != other.bits_per_element || self.elements.len() != other.elements.len() {
+            return Err(BitsFieldError::ShapeMismatch);
+        }
+        for (word, &other) in self.elements.iter_mut().zip(other.elements.iter()) {
+            *word = f(*word, other);
+        }
+        Ok(())
+    }
+
+    /// A copy of the values in `range`, a word at a time.
+    ///
+    /// The copy is rounded up to whole words, its padding values are 0.
+    pub fn slice(&self, range: Range<usize>) -> Result<BitsField<T>, BitsFieldError> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(BitsFieldError::OutOfBounds { index: range.end, len: self.len() });
+        }
+        let mut slice = BitsField::new(self.bits_per_element, range.len())?;
+        let bits = self.bits_per_element as u128;
+        copy_bits(&self.elements, range.start as u128 * bits, &mut slice.elements, 0, range.len() as u128 * bits, self.element_size);
+        Ok(slice)
+    }
+
+    /// Overwrite `count` values from `offset` with the first `count` values
+    /// of `source`, a word at a time.
+    ///
+    /// The count is explicit because fields are rounded up to whole words:
+    /// pasting a slice back must not write its padding values.
+    pub fn splice(&mut self, offset: usize, source: &BitsField<T>, count: usize) -> Result<(), BitsFieldError> {
+        if source.bits_per_element != self.bits_per_element {
+            return Err(BitsFieldError::ShapeMismatch);
+        }
+        if count > source.len() {
+            return Err(BitsFieldError::OutOfBounds { index: count, len: source.len() });
+        }
+        if offset.checked_add(count).is_none_or(|end| end > self.len()) {
+            return Err(BitsFieldError::OutOfBounds { index: offset.saturating_add(count), len: self.len() });
+        }
+        let bits = self.bits_per_element as u128;
+        copy_bits(&source.elements, 0, &mut self.elements, offset as u128 * bits, count as u128 * bits, self.element_size);
+        Ok(())
+    }
+
+    /// Exchange the values at `i` and `j`.
+    pub fn swap(&mut self, i: usize, j: usize) -> Result<(), BitsFieldError> {
+        let (first, second) = (self.bit_offset(i)?, self.bit_offset(j)?);
+        let (bits, size) = (self.bits_per_element, self.element_size);
+        let value_i = read_bits(&self.elements, size, first, bits);
+        let value_j = read_bits(&self.elements, size, second, bits);
+        write_bits(&mut self.elements, size, first, bits, value_j);
+        write_bits(&mut self.elements, size, second, bits, value_i);
+        Ok(())
+    }
+
+    /// Flip every bit of a value in place, alive and dead swap on a one bit
+    /// field. Wider values are inverted, 1 becomes 2 on two bits.
+    pub fn toggle(&mut self, index: usize) -> Result<(), BitsFieldError> {
+        let bit = self.bit_offset(index)?;
+        let (bits, size) = (self.bits_per_element, self.element_size);
+        let value = read_bits(&self.elements, size, bit, bits);
+        write_bits(&mut self.elements, size, bit, bits, !value);
+        Ok(())
+    }
+
+    /// Rotate the values so the one at `n` ends up first, like
+    /// `slice::rotate_left`.
+    ///
+    /// The field is rebuilt one word at a time, whatever `n` is.
+    pub fn rotate_left(&mut self, n: usize) {
+        let len = self.len();
+        if len == 0 {
+            return;
+        }
+        let total = self.value_bits();
+        let shift = (n % len) as u128 * self.bits_per_element as u128;
+        let size = self.element_size;
+        let mut rotated = vec![T::from(0); self.elements.len()];
+        for destination in (0..total).step_by(size) {
+            let count = (size as u128).min(total - destination) as usize;
+            let source = (destination + shift) % total;
+            let value = if source + count as u128 <= total {
+                read_bits(&self.elements, size, source, count)
+            } else {
+                // The word wraps around the end of the values.
+                let first = (total - source) as usize;
+                read_bits(&self.elements, size, source, first) | (read_bits(&self.elements, size, 0, count - first) << first as u8)
+            };
+            write_bits(&mut rotated, size, destination, count, value);
+        }
+        self.elements = rotated;
+    }
+
+    /// Rotate the values so the last `n` ones end up first, like
+    /// `slice::rotate_right`.
+    pub fn rotate_right(&mut self, n: usize) {
+        if !self.is_empty() {
+            self.rotate_left(self.len() - n % self.len());
+        }
+    }
+
+    /// Rotate the values `n` places towards the end, or towards the start
+    /// when `n` is negative.
+    pub fn rotate(&mut self, n: isize) {
+        if n < 0 {
+            self.rotate_left(n.unsigned_abs());
+        } else {
+            self.rotate_right(n as usize);
+        }
+    }
+
+    /// `rotate` on the values in `range` only, the others stay in place.
+    pub fn rotate_range(&mut self, range: Range<usize>, n: isize) -> Result<(), BitsFieldError> {
+        let copy = self.slice(range.clone())?;
+        if range.is_empty() {
+            return Ok(());
+        }
+        // The slice is rounded up to whole words, so its halves are copied
+        // back swapped rather than rotating it.
+        let (len, bits) = (range.len(), self.bits_per_element as u128);
+        let moved = n.rem_euclid(len as isize) as usize;
+        let (start, moved, kept) = (range.start as u128, moved as u128, (len - moved) as u128);
+        copy_bits(&copy.elements, 0, &mut self.elements, (start + moved) * bits, kept * bits, self.element_size);
+        copy_bits(&copy.elements, kept * bits, &mut self.elements, start * bits, moved * bits, self.element_size);
+        Ok(())
+    }
+
+    /// Move every value `n` places towards the start, the first `n` values
+    /// are dropped and the last `n` ones set to `fill`.
+    pub fn shift_left<Output>(&mut self, n: usize, fill: Output)
     where Output: BitFieldCompatible<T> {
-        let (element_index, bit_index) = (&self).convert_index(index)?;
-        
-        // Maybe the data is overlapping on the next element.
-        if (self.element_size - bit_index) < self.bits_per_element {
-            // We need to set some bits on the next element.
-            // There is two masks to build.
-            // The mask of the current element.
-            // The mask of the next element.
-
-            let left_index = self.element_size - bit_index;
-            let right_index = self.bits_per_element - left_index;
-
-            let mut current_mask = T::from(0);
-            for i in 0..left_index {
-                current_mask = current_mask | (T::from(1) << (bit_index + i) as u8)
-            }
-            let mut next_mask = T::from(0);
-            for i in 0..right_index {
-                next_mask = next_mask | (T::from(1) << i as u8)
-            }
-            self.elements[element_index] = (self.elements[element_index] & !current_mask) | ((value.to_type() >> (right_index as u8) << (bit_index - left_index) as u8) & current_mask);
-            self.elements[element_index + 1] = (self.elements[element_index + 1] & !next_mask) | ((value.to_type() << (self.element_size - right_index) as u8) & next_mask);
+        let len = self.len();
+        let n = n.min(len);
+        let total = self.value_bits();
+        let shift = n as u128 * self.bits_per_element as u128;
+        let size = self.element_size;
+        for destination in (0..total - shift).step_by(size) {
+            // Sources are always ahead of destinations, so words can be moved
+            // in place.
+            let count = (size as u128).min(total - shift - destination) as usize;
+            let value = read_bits(&self.elements, size, destination + shift, count);
+            write_bits(&mut self.elements, size, destination, count, value);
+        }
+        self.fill(len - n..len, fill.to_type());
+    }
+
+    /// Move every value `n` places towards the end, the last `n` values are
+    /// dropped and the first `n` ones set to `fill`.
+    pub fn shift_right<Output>(&mut self, n: usize, fill: Output)
+    where Output: BitFieldCompatible<T> {
+        let n = n.min(self.len());
+        let shift = n as u128 * self.bits_per_element as u128;
+        let size = self.element_size;
+        let mut end = self.value_bits();
+        while end > shift {
+            // Going down from the end, sources stay behind destinations.
+            let count = (size as u128).min(end - shift);
+            let start = end - count;
+            let value = read_bits(&self.elements, size, start - shift, count as usize);
+            write_bits(&mut self.elements, size, start, count as usize, value);
+            end = start;
+        }
+        self.fill(0..n, fill.to_type());
+    }
+
+    /// Set the values in `range` to `value`.
+    fn fill(&mut self, range: Range<usize>, value: T) {
+        let bits = self.bits_per_element;
+        for index in range {
+            write_bits(&mut self.elements, self.element_size, index as u128 * bits as u128, bits, value);
+        }
+    }
+
+    /// Bits actually holding values, the end of the last word may be unused.
+    ///
+    /// Bit positions are on 128 bits, like in `locate`.
+    fn value_bits(&self) -> u128 {
+        self.len() as u128 * self.bits_per_element as u128
+    }
+
+    fn bit_offset(&self, index: usize) -> Result<u128, BitsFieldError> {
+        let (element_index, bit_index) = self.convert_index(index)?;
+        Ok(element_index as u128 * self.element_size as u128 + bit_index as u128)
+    }
+
+}
+
+impl BitsField<u8> {
+
+    /// A field of `len` values on the backing word suiting them best.
+    ///
+    /// Wide words let small values be processed a word at a time, but a
+    /// short field would be mostly padding: words are only widened once
+    /// the field fills one, and never past the native word of the target.
+    pub fn auto(bits_per_element: usize, len: usize) -> Result<AutoBitsField, BitsFieldError> {
+        let bits = len.saturating_mul(bits_per_element);
+        if cfg!(target_pointer_width = "64") && bits_per_element <= 2 && bits >= 64 {
+            Ok(AutoBitsField::U64(BitsField::new(bits_per_element, len)?))
+        } else if bits_per_element <= 4 && bits >= 32 {
+            Ok(AutoBitsField::U32(BitsField::new(bits_per_element, len)?))
         } else {
-            // We can set all the bits on the current element.
-            // We need to mask the bits we want.
-            // Let's build the mask.
-            let mut mask = T::from(0);
-            for i in 0..self.bits_per_element {
-                mask = mask | (T::from(1) << (bit_index + i) as u8)
-            }
-            self.elements[element_index] = (self.elements[element_index] & !mask) | ((value.to_type() << (bit_index - self.bits_per_element) as u8) & mask);
+            Ok(AutoBitsField::U8(BitsField::new(bits_per_element, len)?))
+        }
+    }
+
+}
+
+/// A field on the backing word `BitsField::auto` picked.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AutoBitsField {
+    U8(BitsField<u8>),
+    U32(BitsField<u32>),
+    U64(BitsField<u64>),
+}
+
+/// Run `$body` on the field whatever its word.
+macro_rules! on_field {
+    ($self:expr, $field:ident => $body:expr) => {
+        match $self {
+            AutoBitsField::U8($field) => $body,
+            AutoBitsField::U32($field) => $body,
+            AutoBitsField::U64($field) => $body,
         }
+    };
+}
+
+impl AutoBitsField {
+
+    pub fn len(&self) -> usize {
+        on_field!(self, field => field.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        on_field!(self, field => field.is_empty())
+    }
+
+    pub fn layout(&self) -> BitLayout {
+        on_field!(self, field => field.layout())
+    }
+
+    /// Values are at most a byte wide, `u8` reads them all as they are.
+    pub fn get<Output>(&self, index: usize) -> Result<Output, BitsFieldError>
+    where Output: BitFieldCompatible<u8> + BitFieldCompatible<u32> + BitFieldCompatible<u64> {
+        on_field!(self, field => field.get(index))
+    }
+
+    pub fn set<Output>(&mut self, index: usize, value: Output) -> Result<(), BitsFieldError>
+    where Output: BitFieldCompatible<u8> + BitFieldCompatible<u32> + BitFieldCompatible<u64> {
+        on_field!(self, field => field.set(index, value))
+    }
+
+    /// # Safety
+    ///
+    /// `index` must be less than `len()`, see `BitsField::get_unchecked`.
+    #[inline]
+    pub unsafe fn get_unchecked<Output>(&self, index: usize) -> Output
+    where Output: BitFieldCompatible<u8> + BitFieldCompatible<u32> + BitFieldCompatible<u64> {
+        on_field!(self, field => field.get_unchecked(index))
+    }
+
+    pub fn count_matching<Output>(&self, value: Output) -> usize
+    where Output: BitFieldCompatible<u8> + BitFieldCompatible<u32> + BitFieldCompatible<u64> {
+        on_field!(self, field => field.count_matching(value))
+    }
+
+    pub fn find_next<Output>(&self, from: usize, value: Output) -> Option<usize>
+    where Output: BitFieldCompatible<u8> + BitFieldCompatible<u32> + BitFieldCompatible<u64> {
+        on_field!(self, field => field.find_next(from, value))
+    }
+
+    pub fn toggle(&mut self, index: usize) -> Result<(), BitsFieldError> {
+        on_field!(self, field => field.toggle(index))
+    }
+
+    pub fn rotate_range(&mut self, range: Range<usize>, n: isize) -> Result<(), BitsFieldError> {
+        on_field!(self, field => field.rotate_range(range, n))
+    }
+
+    pub fn find_next_not<Output>(&self, from: usize, value: Output) -> Option<usize>
+    where Output: BitFieldCompatible<u8> + BitFieldCompatible<u32> + BitFieldCompatible<u64> {
+        on_field!(self, field => field.find_next_not(from, value))
+    }
+
+    /// The backing words as bytes, values packed from the lowest bit of the
+    /// first byte on little endian targets like wasm.
+    pub fn as_bytes_ptr(&self) -> *const u8 {
+        on_field!(self, field => field.as_ptr().cast::<u8>())
+    }
+
+}
+
+/// Decoded values of a `BitsField`, made by `BitsField::view`.
+///
+/// `Index` has to return a reference, so values are decoded once when the
+/// view is made: the view is a snapshot, later sets on the field do not
+/// show in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitsFieldView<Output> {
+    values: Vec<Output>,
+}
+
+impl<Output> BitsFieldView<Output> {
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Output> {
+        self.values.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Output> {
+        self.values.iter()
+    }
+
+}
+
+/// A row of a `BitsField`, made by `BitsField::row`.
+///
+/// Values are addressed from the row start, a read only checks its index
+/// against the row length.
+pub struct RowView<'a, T>
+where T: BitFieldRepresentation {
+    field: &'a BitsField<T>,
+    start: usize,
+    len: usize,
+}
+
+impl<T> RowView<'_, T>
+where T: BitFieldRepresentation {
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get<Output>(&self, x: usize) -> Option<Output>
+    where Output: BitFieldCompatible<T> {
+        // The whole row was checked to be in the field.
+        (x < self.len).then(|| unsafe { self.field.get_unchecked(self.start + x) })
+    }
+
+    pub fn iter<Output>(&self) -> impl Iterator<Item = Output> + '_
+    where Output: BitFieldCompatible<T> {
+        (0..self.len).map(move |x| unsafe { self.field.get_unchecked(self.start + x) })
+    }
+
+}
+
+/// A row of a `BitsField` to set values of, made by `BitsField::row_mut`.
+pub struct RowViewMut<'a, T>
+where T: BitFieldRepresentation {
+    field: &'a mut BitsField<T>,
+    start: usize,
+    len: usize,
+}
+
+impl<T> RowViewMut<'_, T>
+where T: BitFieldRepresentation {
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get<Output>(&self, x: usize) -> Option<Output>
+    where Output: BitFieldCompatible<T> {
+        (x < self.len).then(|| unsafe { self.field.get_unchecked(self.start + x) })
+    }
+
+    pub fn set<Output>(&mut self, x: usize, value: Output) -> Result<(), BitsFieldError>
+    where Output: BitFieldCompatible<T> {
+        if x >= self.len {
+            return Err(BitsFieldError::OutOfBounds { index: x, len: self.len });
+        }
+        unsafe { self.field.set_unchecked(self.start + x, value) };
         Ok(())
     }
 
 }
 
+impl<Output> Index<usize> for BitsFieldView<Output> {
+    type Output = Output;
+
+    fn index(&self, index: usize) -> &Output {
+        &self.values[index]
+    }
+}
+
+/// Bitwise operations between fields, a word at a time, to mask or merge
+/// regions.
+///
+/// They panic when the fields do not have the same bits per element and
+/// length, `diff` reports it as an error.
+macro_rules! impl_assign_op {
+    ($($trait:ident, $method:ident, $op:tt);*) => {$(
+        impl<T> $trait<&BitsField<T>> for BitsField<T>
+        where T: BitFieldRepresentation {
+            fn $method(&mut self, other: &BitsField<T>) {
+                if let Err(error) = self.combine(other, |a, b| a $op b) {
+                    panic!("{}", error);
+                }
+            }
+        }
+    )*};
+}
+
+impl_assign_op!(BitAndAssign, bitand_assign, &; BitOrAssign, bitor_assign, |; BitXorAssign, bitxor_assign, ^);
+
+/// An empty field of one bit values.
+impl<T> Default for BitsField<T>
+where T: BitFieldRepresentation {
+    fn default() -> Self {
+        // One bit always fits in a word.
+        BitsField::new(1, 0).unwrap()
+    }
+}
+
+/// Fields are equal when they have the same bit width and values, padding
+/// values at the end of the last word included.
+impl<T> PartialEq for BitsField<T>
+where T: BitFieldRepresentation {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits_per_element == other.bits_per_element && self.elements == other.elements
+    }
+}
+
+impl<T> Eq for BitsField<T>
+where T: BitFieldRepresentation + Eq {}
+
+impl<T> Hash for BitsField<T>
+where T: BitFieldRepresentation + Hash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits_per_element.hash(state);
+        self.elements.hash(state);
+    }
+}
+
+/// Shows the decoded values rather than the packed words.
+impl<T> fmt::Debug for BitsField<T>
+where T: BitFieldRepresentation + fmt::Debug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BitsField")
+            .field("bits_per_element", &self.bits_per_element)
+            .field("values", &(0..self.len()).map(|index| self.read(index)).collect::<Vec<T>>())
+            .finish()
+    }
+}
+
+/// Fields are saved as their bit width and packed words.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "BitsField")]
+struct RawFieldRef<'a, T> {
+    bits_per_element: usize,
+    elements: &'a [T],
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "BitsField")]
+struct RawField<T> {
+    bits_per_element: usize,
+    elements: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for BitsField<T>
+where T: BitFieldRepresentation + serde::Serialize {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        RawFieldRef { bits_per_element: self.bits_per_element, elements: &self.elements }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for BitsField<T>
+where T: BitFieldRepresentation + serde::Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        let raw = RawField::<T>::deserialize(deserializer)?;
+        BitsField::from_raw_parts(raw.bits_per_element, &raw.elements).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Mask of the `count` lowest bits, `count` being at most the word size.
+fn low_mask<T>(count: usize, element_size: usize) -> T
+where T: BitFieldRepresentation {
+    if count >= element_size {
+        !T::from(0)
+    } else {
+        !(!T::from(0) << count as u8)
+    }
+}
+
+/// `value` in every slot of a word, and the lowest bit of every slot.
+fn repeat<T>(value: T, bits: usize, size: usize) -> (T, T)
+where T: BitFieldRepresentation {
+    let (mut repeated, mut lowest) = (T::from(0), T::from(0));
+    for slot in (0..size).step_by(bits) {
+        repeated = repeated | (value << slot as u8);
+        lowest = lowest | (T::from(1) << slot as u8);
+    }
+    (repeated, lowest)
+}
+
+/// The lowest bit of every slot of `element` other than the value
+/// `repeated` holds.
+fn differing<T>(element: T, repeated: T, lowest: T, bits: usize) -> T
+where T: BitFieldRepresentation {
+    // Fold the differing bits of every value onto its lowest bit.
+    let mut different = element ^ repeated;
+    let mut shift = 1;
+    while shift < bits {
+        different = different | (different >> shift as u8);
+        shift *= 2;
+    }
+    different & lowest
+}
+
+/// Read `count` bits (at most a word) starting at bit `bit`, possibly over two
+/// words.
+fn read_bits<T>(elements: &[T], element_size: usize, bit: u128, count: usize) -> T
+where T: BitFieldRepresentation {
+    let (word, offset) = split(bit, element_size);
+    let mut value = elements[word] >> offset as u8;
+    if offset + count > element_size {
+        value = value | (elements[word + 1] << (element_size - offset) as u8);
+    }
+    value & low_mask(count, element_size)
+}
+
+/// Write the `count` lowest bits of `value` at bit `bit`, possibly over two
+/// words.
+fn write_bits<T>(elements: &mut [T], element_size: usize, bit: u128, count: usize, value: T)
+where T: BitFieldRepresentation {
+    let (word, offset) = split(bit, element_size);
+    let mask: T = low_mask(count, element_size);
+    let value = value & mask;
+    elements[word] = (elements[word] & !(mask << offset as u8)) | (value << offset as u8);
+    if offset + count > element_size {
+        let written = element_size - offset;
+        let rest: T = low_mask(count - written, element_size);
+        elements[word + 1] = (elements[word + 1] & !rest) | (value >> written as u8);
+    }
+}
+
+/// Copy `count` bits from bit `from` of `source` to bit `to` of
+/// `destination`, at most a word at a time.
+fn copy_bits<T>(source: &[T], from: u128, destination: &mut [T], to: u128, count: u128, element_size: usize)
+where T: BitFieldRepresentation {
+    let mut copied = 0;
+    while copied < count {
+        let chunk = (element_size as u128).min(count - copied) as usize;
+        let value = read_bits(source, element_size, from + copied, chunk);
+        write_bits(destination, element_size, to + copied, chunk, value);
+        copied += chunk as u128;
+    }
+}
+
+/// Number of elements needed to store `size` values, or `None` when the
+/// field could not be addressed with an usize.
+fn elements_for(size: usize, bits_per_element: usize, element_size: usize) -> Option<usize> {
+    let bits = size as u128 * bits_per_element as u128;
+    let count = usize::try_from(bits.div_ceil(element_size as u128)).ok()?;
+    // The length of the field, which rounds up to whole elements, must fit too.
+    usize::try_from(count as u128 * element_size as u128 / bits_per_element as u128).ok()?;
+    Some(count)
+}
+
+/// Element and bit index of the value at `index`.
+///
+/// The bit offset is computed on 128 bits: on 32 bits targets (wasm) a field
+/// bigger than 512MB has more bits than an usize can count.
+fn locate(index: usize, bits_per_element: usize, element_size: usize) -> (usize, usize) {
+    split(index as u128 * bits_per_element as u128, element_size)
+}
+
+/// Element and bit index of bit `bit` of the elements taken as one string
+/// of bits, bit positions being on 128 bits for the same reason as in
+/// `locate`.
+fn split(bit: u128, element_size: usize) -> (usize, usize) {
+    match usize::try_from(bit) {
+        // Most fields have less bits than an usize counts, and skip the
+        // slower 128 bits division.
+        Ok(bit) => (bit / element_size, bit % element_size),
+        Err(_) => ((bit / element_size as u128) as usize, (bit % element_size as u128) as usize),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::BitFieldCompatible;
+    use super::{elements_for, locate, split, BitFieldCompatible};
 
 
     #[derive(Debug, PartialEq)]
@@ -180,7 +1054,7 @@ mod tests {
         assert_eq!(bits_field.elements.len(), 2);
 
         // Check if the bits_field is not empty.
-        assert_eq!(bits_field.is_empty(), false);
+        assert!(!bits_field.is_empty());
 
         // Set the even elements to Alive.
         for i in 0..bits_field.len() {
@@ -200,4 +1074,386 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_sizing_near_usize_limits() {
+        assert_eq!(elements_for(16, 1, 8), Some(2));
+        assert_eq!(elements_for(17, 3, 8), Some(7));
+        assert_eq!(elements_for(usize::MAX - 7, 1, 8), Some(usize::MAX / 8));
+        // Rounding up to whole elements would give more than usize::MAX values.
+        assert_eq!(elements_for(usize::MAX, 1, 8), None);
+        assert_eq!(elements_for(usize::MAX, 1, 64), None);
+        assert_eq!(elements_for(usize::MAX, 8, 8), Some(usize::MAX));
+        assert_eq!(elements_for(usize::MAX, 3, 8), None);
+    }
+
+    #[test]
+    fn test_locate_near_usize_limits() {
+        assert_eq!(locate(5, 3, 8), (1, 7));
+        assert_eq!(locate(usize::MAX, 8, 8), (usize::MAX, 0));
+        let expected = usize::MAX as u128 * 3;
+        assert_eq!(locate(usize::MAX, 3, 64), ((expected / 64) as usize, (expected % 64) as usize));
+    }
+
+    /// Bit positions of fields over 512MB do not fit in a 32 bits usize,
+    /// the words they are in do.
+    #[test]
+    fn test_split_past_usize() {
+        assert_eq!(split(13, 8), (1, 5));
+        assert_eq!(split(usize::MAX as u128 * 8 + 7, 8), (usize::MAX, 7));
+        let bit = (usize::MAX as u128 + 1) * 3 + 5;
+        assert_eq!(split(bit, 64), ((bit / 64) as usize, (bit % 64) as usize));
+        assert_eq!(split(bit, 32).1, 5);
+    }
+
+    /// Past 2^53 a `f64` can not hold every integer, rounding through one
+    /// would lose the last word of these fields.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_sizing_past_f64_precision() {
+        let size = (1usize << 53) + 1;
+        assert_eq!(elements_for(size, 1, 64), Some((1 << 47) + 1));
+        assert_eq!(elements_for(size, 3, 64), Some(((size as u128 * 3).div_ceil(64)) as usize));
+        assert_eq!(locate(size, 1, 64), (1 << 47, 1));
+        assert_eq!(locate(size, 3, 64), (((size as u128 * 3) / 64) as usize, 3));
+        // Five gigabits of 2 bits values.
+        assert_eq!(elements_for(5 << 29, 2, 32), Some(5 << 25));
+        assert_eq!(locate((5 << 29) - 1, 2, 32), ((5 << 25) - 1, 30));
+    }
+
+    #[test]
+    fn test_default_and_capacity() {
+        let field = super::BitsField::<u32>::default();
+        assert!(field.is_empty());
+        assert_eq!(field.bits_per_element(), 1);
+
+        let mut field = super::BitsField::<u8>::with_capacity(3, 100).unwrap();
+        assert!(field.is_empty());
+        assert_eq!(field.bits_per_element(), 3);
+        field.resize(100).unwrap();
+        assert_eq!(field.get::<u8>(99).unwrap(), 0);
+        assert!(super::BitsField::<u8>::with_capacity(9, 1).is_err());
+        assert!(super::BitsField::<u64>::with_capacity(1, usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_oversized_fields_are_errors() {
+        assert!(super::BitsField::<u8>::new(4, usize::MAX).is_err());
+        // Addressable, but far too big to allocate.
+        assert!(super::BitsField::<u64>::new(1, usize::MAX / 2).is_err());
+    }
+
+    #[test]
+    fn test_swap_rotate_shift() {
+        // 2 bits values with shifts that are not whole words.
+        let mut field = super::BitsField::<u8>::new(2, 12).unwrap();
+        let model: Vec<u8> = (0..12).map(|i| i % 4).collect();
+        for (i, &value) in model.iter().enumerate() {
+            field.set(i, value).unwrap();
+        }
+        let values = |field: &super::BitsField<u8>| (0..field.len()).map(|i| field.get::<u8>(i).unwrap()).collect::<Vec<_>>();
+
+        field.toggle(2).unwrap();
+        assert_eq!(field.get::<u8>(2).unwrap(), 3 - model[2]);
+        field.toggle(2).unwrap();
+        assert!(field.toggle(12).is_err());
+        field.swap(1, 10).unwrap();
+        let mut expected = model.clone();
+        expected.swap(1, 10);
+        assert_eq!(values(&field), expected);
+        assert!(field.swap(1, 12).is_err());
+
+        field.rotate_left(15);
+        expected.rotate_left(3);
+        assert_eq!(values(&field), expected);
+
+        field.shift_left(5, 3u8);
+        expected.drain(..5);
+        expected.extend([3; 5]);
+        assert_eq!(values(&field), expected);
+
+        field.shift_right(2, 1u8);
+        expected.truncate(10);
+        expected.splice(..0, [1; 2]);
+        assert_eq!(values(&field), expected);
+
+        field.rotate(-4);
+        expected.rotate_left(4);
+        assert_eq!(values(&field), expected);
+        field.rotate(7);
+        expected.rotate_right(7);
+        assert_eq!(values(&field), expected);
+
+        field.rotate_range(3..8, 2).unwrap();
+        expected[3..8].rotate_right(2);
+        assert_eq!(values(&field), expected);
+        assert!(field.rotate_range(3..13, 1).is_err());
+
+        field.shift_left(100, 0u8);
+        assert_eq!(values(&field), vec![0; 12]);
+        field.shift_right(100, 2u8);
+        assert_eq!(values(&field), vec![2; 12]);
+    }
+
+    #[test]
+    fn test_iter_and_update() {
+        let mut field = super::BitsField::<u64>::new(2, 40).unwrap();
+        field.update(|index, _: u8| (index % 3) as u8);
+        assert_eq!(field.iter::<u8>().take(5).collect::<Vec<_>>(), vec![0, 1, 2, 0, 1]);
+        assert_eq!(field.iter::<bool>().filter(|&alive| alive).count(), field.len() * 2 / 3);
+        field.update(|_, value: u8| value.saturating_sub(1));
+        assert_eq!((0..6).map(|i| field.get::<u8>(i).unwrap()).collect::<Vec<_>>(), vec![0, 0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut field = super::BitsField::<u32>::new(4, 20).unwrap();
+        field.update(|index, _: u8| (index % 5) as u8);
+        // The field rounds up to 24 values, 0 to 4 four times then 0 to 3.
+        assert_eq!(field.count_matching(3u8), 5);
+        assert_eq!(field.count_matching(0u8), 5);
+        assert_eq!(field.count_ones(), 4 * (1 + 1 + 2 + 1) + (1 + 1 + 2));
+
+        let mut straddling = super::BitsField::<u8>::new(3, 10).unwrap();
+        straddling.set(4, 5u8).unwrap();
+        straddling.set(9, 5u8).unwrap();
+        assert_eq!(straddling.count_matching(5u8), 2);
+        assert_eq!(straddling.count_ones(), 4);
+    }
+
+    #[test]
+    fn test_find_next() {
+        let mut field = super::BitsField::<u64>::new(2, 200).unwrap();
+        for &index in &[3, 40, 130] {
+            field.set(index, 2u8).unwrap();
+        }
+        assert_eq!(field.find_next_not(0, 0u8), Some(3));
+        assert_eq!(field.find_next_not(4, 0u8), Some(40));
+        assert_eq!(field.find_next(41, 2u8), Some(130));
+        assert_eq!(field.find_next_not(131, 0u8), None);
+        assert_eq!(field.find_next(3, 0u8), Some(4));
+        assert_eq!(field.find_next(field.len(), 0u8), None);
+
+        let mut straddling = super::BitsField::<u8>::new(3, 10).unwrap();
+        straddling.set(7, 5u8).unwrap();
+        assert_eq!(straddling.find_next(0, 5u8), Some(7));
+        assert_eq!(straddling.find_next_not(8, 0u8), None);
+    }
+
+    /// Random sets at every width of a word, checked against a plain
+    /// vector, values straddling words included.
+    #[cfg(not(target_arch = "wasm32"))]
+    macro_rules! round_trip {
+        ($($name:ident: $t:ty),*) => {$(
+            proptest::proptest! {
+                #[test]
+                fn $name(len in 1usize..300, sets in proptest::collection::vec((proptest::num::usize::ANY, proptest::num::u128::ANY), 0..100)) {
+                    let size = std::mem::size_of::<$t>() * 8;
+                    for bits in 1..=size {
+                        let mut field = super::BitsField::<$t>::new(bits, len).unwrap();
+                        let mut model = vec![0 as $t; field.len()];
+                        let mask = if bits == size { <$t>::MAX } else { (1 << bits) - 1 };
+                        for &(index, value) in &sets {
+                            let (index, value) = (index % field.len(), value as $t);
+                            field.set(index, value).unwrap();
+                            model[index] = value & mask;
+                        }
+                        for (index, &value) in model.iter().enumerate() {
+                            proptest::prop_assert_eq!(field.get::<$t>(index).unwrap(), value, "{} bits", bits);
+                            proptest::prop_assert_eq!(unsafe { field.get_unchecked::<$t>(index) }, value, "{} bits", bits);
+                        }
+                        let probe = model[0];
+                        proptest::prop_assert_eq!(field.count_matching(probe), model.iter().filter(|&&value| value == probe).count());
+                        proptest::prop_assert_eq!(field.find_next_not(0, 0 as $t), model.iter().position(|&value| value != 0));
+                    }
+                }
+            }
+        )*};
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    round_trip!(test_round_trip_u8: u8, test_round_trip_u16: u16, test_round_trip_u32: u32, test_round_trip_u64: u64, test_round_trip_u128: u128);
+
+    #[test]
+    fn test_resize() {
+        let mut field = super::BitsField::<u8>::new(2, 6).unwrap();
+        field.update(|_, _: u8| 3u8);
+        field.resize(20).unwrap();
+        assert_eq!(field.len(), 20);
+        assert_eq!(field.iter::<u8>().collect::<Vec<_>>()[6..10], [3, 3, 0, 0]);
+
+        field.resize(3).unwrap();
+        assert_eq!(field.len(), 4);
+        field.resize(8).unwrap();
+        assert_eq!(field.iter::<u8>().collect::<Vec<_>>(), vec![3, 3, 3, 0, 0, 0, 0, 0]);
+        assert!(field.resize(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_raw_parts() {
+        let mut field = super::BitsField::<u16>::new(4, 8).unwrap();
+        field.set(5, 9u8).unwrap();
+        assert_eq!(field.as_slice(), &[0, 0x90]);
+        let copy = super::BitsField::<u16>::from_raw_parts(4, field.as_slice()).unwrap();
+        assert_eq!(copy.get::<u8>(5).unwrap(), 9);
+        assert_eq!(copy.len(), 8);
+        assert_eq!(field.into_vec(), vec![0, 0x90]);
+        assert!(super::BitsField::<u16>::from_raw_parts(17, &[0]).is_err());
+
+        // Five 3 bits values fill 15 bits, the last bit is not a value.
+        let stray = super::BitsField::<u16>::from_raw_parts(3, &[0x8001]).unwrap();
+        assert_eq!(stray.as_slice(), &[1]);
+        assert_eq!(stray.count_ones(), 1);
+        assert_eq!(stray, super::BitsField::<u16>::from_raw_parts(3, &[1]).unwrap());
+    }
+
+    #[test]
+    fn test_wide_values() {
+        // 24 bits values straddle every other u32 word.
+        let mut field = super::BitsField::<u32>::new(24, 6).unwrap();
+        for i in 0..6 {
+            field.set(i, 0xABCDEF - i as u32).unwrap();
+        }
+        assert_eq!((0..6).map(|i| field.get::<u32>(i).unwrap()).collect::<Vec<_>>(), (0..6).map(|i| 0xABCDEF - i).collect::<Vec<_>>());
+        field.set(1, 0x1FFFFFFu32).unwrap();
+        assert_eq!(field.get::<u32>(1).unwrap(), 0xFFFFFF);
+        assert_eq!(field.get::<u32>(2).unwrap(), 0xABCDED);
+
+        let whole = super::BitsField::<u128>::new(128, 2).unwrap();
+        assert_eq!(whole.len(), 2);
+        assert!(super::BitsField::<u16>::new(17, 2).is_err());
+    }
+
+    #[test]
+    fn test_equality_and_debug() {
+        use std::collections::HashSet;
+
+        let mut a = super::BitsField::<u8>::new(2, 4).unwrap();
+        let mut b = super::BitsField::<u8>::new(2, 4).unwrap();
+        a.set(1, 3u8).unwrap();
+        assert_ne!(a, b);
+        b.set(1, 3u8).unwrap();
+        assert_eq!(a, b);
+        assert_ne!(super::BitsField::<u8>::new(1, 8).unwrap(), super::BitsField::<u8>::new(2, 4).unwrap());
+        assert_eq!(HashSet::from([a.clone(), b]).len(), 1);
+        assert_eq!(format!("{:?}", a), "BitsField { bits_per_element: 2, values: [0, 3, 0, 0] }");
+    }
+
+    #[test]
+    fn test_words() {
+        let mut field = super::BitsField::<u64>::new(2, 40).unwrap();
+        assert_eq!(field.layout(), super::BitLayout { bits_per_element: 2, word_bits: 64, values_per_word: Some(32), len: 64 });
+        // Set the second value of the second word.
+        field.as_mut_slice()[1] = 0b01 << 2;
+        assert_eq!(field.get::<u8>(33).unwrap(), 1);
+        assert_eq!(super::BitsField::<u8>::new(3, 8).unwrap().layout().values_per_word, None);
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let field = |values: &[u8]| {
+            let mut field = super::BitsField::<u8>::new(2, 4).unwrap();
+            values.iter().enumerate().for_each(|(i, &value)| field.set(i, value).unwrap());
+            field
+        };
+        let (a, b) = (field(&[1, 2, 3, 0]), field(&[1, 1, 2, 0]));
+        let diff = a.diff(&b).unwrap();
+        assert_eq!(diff.iter::<u8>().collect::<Vec<_>>(), vec![0, 3, 1, 0]);
+        assert_eq!(diff.count_matching(0u8), 2);
+
+        let mut masked = a.clone();
+        masked &= &field(&[3, 0, 3, 0]);
+        assert_eq!(masked, field(&[1, 0, 3, 0]));
+        masked |= &b;
+        assert_eq!(masked, field(&[1, 1, 3, 0]));
+        masked ^= &masked.clone();
+        assert_eq!(masked.count_ones(), 0);
+        assert!(a.diff(&super::BitsField::<u8>::new(2, 8).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_auto() {
+        use super::{AutoBitsField, BitsField};
+
+        assert!(matches!(BitsField::auto(1, 16).unwrap(), AutoBitsField::U8(_)));
+        assert!(matches!(BitsField::auto(4, 64).unwrap(), AutoBitsField::U32(_)));
+        assert!(matches!(BitsField::auto(8, 1000).unwrap(), AutoBitsField::U8(_)));
+        let mut wide = BitsField::auto(2, 100).unwrap();
+        assert_eq!(wide.layout().word_bits, if cfg!(target_pointer_width = "64") { 64 } else { 32 });
+        wide.set(99, 3u8).unwrap();
+        assert_eq!(wide.get::<u8>(99).unwrap(), 3);
+        assert_eq!(wide.count_matching(0u8), wide.len() - 1);
+    }
+
+    #[test]
+    fn test_slice_splice() {
+        let mut field = super::BitsField::<u8>::new(3, 24).unwrap();
+        field.update(|index, _: u8| (index % 8) as u8);
+        let slice = field.slice(5..15).unwrap();
+        assert_eq!(slice.iter::<u8>().take(10).collect::<Vec<_>>(), vec![5, 6, 7, 0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(slice.iter::<u8>().skip(10).filter(|&value| value != 0).count(), 0);
+
+        // Paste it back further, only its 10 values.
+        field.splice(12, &slice, 10).unwrap();
+        assert_eq!(field.iter::<u8>().collect::<Vec<_>>()[10..], [2, 3, 5, 6, 7, 0, 1, 2, 3, 4, 5, 6, 6, 7]);
+        assert!(field.splice(20, &slice, 10).is_err());
+        assert!(field.slice(20..30).is_err());
+    }
+
+    #[test]
+    fn test_view() {
+        let mut field = super::BitsField::<u8>::new(1, 8).unwrap();
+        field.set(2, TwoBitsState::Alive).unwrap();
+        let view = field.view::<TwoBitsState>();
+        assert_eq!(view.len(), 8);
+        assert_eq!(view[2], TwoBitsState::Alive);
+        assert_eq!(view[3], TwoBitsState::Dead);
+        assert_eq!(view.get(8), None);
+        assert_eq!(view.iter().filter(|&state| *state == TwoBitsState::Alive).count(), 1);
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut field = super::BitsField::<u32>::new(3, 30).unwrap();
+        {
+            let mut row = field.row_mut(2, 10).unwrap();
+            row.set(0, 5u8).unwrap();
+            row.set(9, 7u8).unwrap();
+            assert!(row.set(10, 1u8).is_err());
+        }
+        assert_eq!(field.get::<u8>(20).unwrap(), 5);
+        let row = field.row(2, 10).unwrap();
+        assert_eq!(row.get::<u8>(9), Some(7));
+        assert_eq!(row.get::<u8>(10), None);
+        assert_eq!(row.iter::<u8>().filter(|&value| value != 0).count(), 2);
+        assert!(field.row(3, 10).is_err());
+        assert!(field.row(usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_unchecked() {
+        // 2 bits values fill words, 3 bits ones straddle them.
+        for bits in [2, 3] {
+            let mut field = super::BitsField::<u8>::new(bits, 20).unwrap();
+            for i in 0..20 {
+                unsafe { field.set_unchecked(i, (i % 5) as u8) };
+            }
+            field.set(7, 0xFFu8).unwrap();
+            for i in 0..20 {
+                let expected = if i == 7 { (1 << bits) - 1 } else { (i % 5) as u8 & ((1 << bits) - 1) };
+                assert_eq!(unsafe { field.get_unchecked::<u8>(i) }, expected);
+                assert_eq!(field.get::<u8>(i).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rotate_across_wide_words() {
+        let mut field = super::BitsField::<u64>::new(1, 128).unwrap();
+        field.set(3, true).unwrap();
+        field.set(100, true).unwrap();
+        field.rotate_left(70);
+        let alive: Vec<usize> = (0..128).filter(|&i| field.get::<bool>(i).unwrap()).collect();
+        assert_eq!(alive, vec![30, 61]);
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! Games for chat bots, one per channel, driven by text commands.
+//!
+//! The bot only carries messages: it hands every message of a channel to
+//! `BotSessions::handle` and posts the reply back.
+
+use std::collections::HashMap;
+
+use super::rules::{self, Rule};
+use super::{InitPolicy, ParseError, TextRenderer, Universe, UniverseError, Xoshiro256};
+
+const HELP: &str = "commands: new <width>x<height> [rule], random [density], step [generations], show, end";
+
+/// A message to a channel game.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum BotCommand {
+    /// Start a dead game, Conway's Life unless a rulestring is given.
+    New { width: usize, height: usize, rule: Option<String> },
+    /// Fill the board at random, 30% of the cells alive by default.
+    Random { density: f64 },
+    Step { generations: u64 },
+    Show,
+    End,
+    Help,
+}
+
+impl BotCommand {
+
+    /// Parse a message like `new 20x10 B36/S23` or `step 5`.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        // Errors are on the word they are about, or past the end. Words are
+        // slices of `text`, their offset is the distance between both.
+        let offset = |word: Option<&str>| word.map_or(text.len(), |word| word.as_ptr() as usize - text.as_ptr() as usize);
+        let error = |message, word| ParseError::at(message, 1, text, offset(word));
+        let mut words = text.split_whitespace();
+        let command = words.next().ok_or_else(|| error("empty command", None))?;
+        let argument = words.next();
+        let number = |message| error(message, argument);
+        match command.to_lowercase().as_str() {
+            "new" => {
+                let size = argument.ok_or_else(|| error("new needs a size like 20x10", None))?;
+                let (width, height) = size.split_once('x').ok_or_else(|| number("size must look like 20x10"))?;
+                Ok(BotCommand::New {
+                    width: width.parse().map_err(|_| number("size must look like 20x10"))?,
+                    height: height.parse().map_err(|_| number("size must look like 20x10"))?,
+                    rule: words.next().map(str::to_string),
+                })
+            }
+            "random" => Ok(BotCommand::Random { density: argument.map_or(Ok(0.3), str::parse).map_err(|_| number("density must be a number"))? }),
+            "step" => Ok(BotCommand::Step { generations: argument.map_or(Ok(1), str::parse).map_err(|_| number("generations must be a number"))? }),
+            "show" => Ok(BotCommand::Show),
+            "end" => Ok(BotCommand::End),
+            "help" => Ok(BotCommand::Help),
+            _ => Err(error("unknown command, try help", Some(command))),
+        }
+    }
+
+}
+
+struct Game {
+    universe: Universe,
+    rule: Box<dyn Rule>,
+    rng: Xoshiro256,
+}
+
+/// Games by channel, with the limits keeping replies postable.
+pub struct BotSessions {
+    games: HashMap<String, Game>,
+    renderer: TextRenderer,
+    /// Longest reply in characters, boards are shrunk to fit.
+    pub max_chars: usize,
+    /// Largest board width and height.
+    pub max_size: usize,
+    /// Most generations of a single step command.
+    pub max_generations: u64,
+    seed: u64,
+}
+
+impl BotSessions {
+
+    /// Emoji boards, under Discord's 2000 characters message limit.
+    pub fn new() -> Self {
+        BotSessions::with_renderer(TextRenderer::emoji())
+    }
+
+    pub fn with_renderer(renderer: TextRenderer) -> Self {
+        BotSessions { games: HashMap::new(), renderer, max_chars: 2000, max_size: 64, max_generations: 1000, seed: 0 }
+    }
+
+    /// Channels with a game.
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// The reply to a message of `channel`, errors included.
+    ///
+    /// Parse errors are replied without their position, commands are a
+    /// single short line.
+    pub fn handle(&mut self, channel: &str, text: &str) -> String {
+        match BotCommand::parse(text).map_err(UniverseError::from).and_then(|command| self.run(channel, command)) {
+            Ok(reply) => reply,
+            Err(UniverseError::Parse(error)) => error.message.to_string(),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    pub fn run(&mut self, channel: &str, command: BotCommand) -> Result<String, UniverseError> {
+        match command {
+            BotCommand::New { width, height, rule } => {
+                if width == 0 || height == 0 || width > self.max_size || height > self.max_size {
+                    return Err(UniverseError::InvalidArgument("board size out of the allowed range"));
+                }
+                let rule = rules::parse(rule.as_deref().unwrap_or("B3/S23"))?;
+                self.seed += 1;
+                let game = Game { universe: Universe::with_states(width, height, rule.states())?, rule, rng: Xoshiro256::new(self.seed) };
+                self.games.insert(channel.to_string(), game);
+            }
+            BotCommand::Random { density } => {
+                let game = self.games.get_mut(channel).ok_or(UniverseError::InvalidArgument("no game here, start one with new"))?;
+                game.universe.initialize(&InitPolicy::Random { alive_probability: density }, &mut game.rng)?;
+            }
+            BotCommand::Step { generations } => {
+                if generations > self.max_generations {
+                    return Err(UniverseError::InvalidArgument("too many generations at once"));
+                }
+                let game = self.games.get_mut(channel).ok_or(UniverseError::InvalidArgument("no game here, start one with new"))?;
+                game.universe.advance_generations(game.rule.as_ref(), generations)?;
+            }
+            BotCommand::Show => {}
+            BotCommand::End => {
+                self.games.remove(channel).ok_or(UniverseError::InvalidArgument("no game here"))?;
+                return Ok("game over".to_string());
+            }
+            BotCommand::Help => return Ok(HELP.to_string()),
+        }
+        self.render(channel)
+    }
+
+    /// The board of a channel with its generation, shrunk until it fits in
+    /// `max_chars`.
+    pub fn render(&self, channel: &str) -> Result<String, UniverseError> {
+        let game = self.games.get(channel).ok_or(UniverseError::InvalidArgument("no game here, start one with new"))?;
+        let header = format!("generation {}, population {}\n", game.universe.generation(), game.universe.population());
+        let mut width = game.universe.width();
+        loop {
+            let board = self.renderer.clone().with_max_width(Some(width)).render(&game.universe)?;
+            if header.chars().count() + board.chars().count() <= self.max_chars {
+                return Ok(header + &board);
+            }
+            if width == 1 {
+                return Err(UniverseError::InvalidArgument("max_chars too small for any board"));
+            }
+            width /= 2;
+        }
+    }
+
+}
+
+impl Default for BotSessions {
+    fn default() -> Self {
+        BotSessions::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BotCommand, BotSessions};
+    use crate::game_of_life::TextRenderer;
+
+    #[test]
+    fn test_session() {
+        let mut sessions = BotSessions::with_renderer(TextRenderer::ascii());
+        assert_eq!(sessions.handle("general", "step"), "no game here, start one with new");
+        assert_eq!(sessions.handle("general", "new 4x2"), "generation 0, population 0\n....\n....\n");
+        sessions.handle("random", "new 30x30 B36/S23");
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.handle("random", "random 0.5").starts_with("generation 0, population "));
+        assert!(sessions.handle("random", "step 3").starts_with("generation 3,"));
+
+        sessions.max_chars = 100;
+        let reply = sessions.handle("random", "show");
+        assert!(reply.chars().count() <= 100);
+        assert_eq!(sessions.handle("general", "new 100x100"), "board size out of the allowed range");
+        assert_eq!(sessions.handle("general", "end"), "game over");
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(BotCommand::parse("step 5"), Ok(BotCommand::Step { generations: 5 }));
+        let error = BotCommand::parse("new  20y10").unwrap_err();
+        assert_eq!((error.message, error.column), ("size must look like 20x10", 6));
+        assert_eq!(BotCommand::parse("new").unwrap_err().column, 4);
+        assert_eq!(BotSessions::new().handle("general", "jump"), "unknown command, try help");
+    }
+
+}
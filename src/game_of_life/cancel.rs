@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wasm_bindgen::prelude::*;
+
+use super::UniverseError;
+
+/// Shared flag to stop a long running operation early.
+///
+/// Clones share the flag: keep one to call `cancel` on, from another thread
+/// or from JavaScript for a "Stop" button, and pass the other one to the
+/// operation, which checks it between two units of work.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+#[wasm_bindgen]
+impl CancelToken {
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    #[wasm_bindgen(js_name = isCancelled)]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+}
+
+impl CancelToken {
+
+    /// `Err` once cancelled, so operations can bail out with `?`.
+    pub fn check(&self) -> Result<(), UniverseError> {
+        if self.is_cancelled() {
+            Err(UniverseError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CancelToken, UniverseError};
+
+    #[test]
+    fn test_clones_share_the_flag() {
+        let token = CancelToken::new();
+        let worker = token.clone();
+        assert!(worker.check().is_ok());
+
+        std::thread::spawn(move || token.cancel()).join().unwrap();
+        assert!(worker.is_cancelled());
+        assert_eq!(worker.check(), Err(UniverseError::Cancelled));
+    }
+
+}
@@ -0,0 +1,247 @@
+//! Searchable collection of patterns, for pattern browsers.
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use super::{Pattern, Size, StaticPattern, Storage, UniverseError};
+
+/// Patterns every catalog can start from: name, period, tags and cells.
+const BUILTIN: [(&str, usize, &[&str], StaticPattern); 6] = [
+    ("Block", 1, &["still life"], crate::pattern!("x = 2, y = 2\n2o$2o!")),
+    ("Blinker", 2, &["oscillator"], crate::pattern!("x = 3, y = 1\n3o!")),
+    ("Glider", 4, &["spaceship"], crate::pattern!("x = 3, y = 3\nbo$2bo$3o!")),
+    ("Lightweight spaceship", 4, &["spaceship"], crate::pattern!("x = 5, y = 4\nbo2bo$o4b$o3bo$4o!")),
+    ("R-pentomino", 0, &["methuselah"], crate::pattern!("x = 3, y = 3\nb2o$2ob$bo!")),
+    ("Gosper glider gun", 30, &["gun", "oscillator"], crate::pattern!("
+x = 36, y = 9
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4bobo$
+10bo5bo7bo$11bo3bo$12b2o!
+")),
+];
+
+/// A pattern of a catalog and how it is indexed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogEntry {
+    pub pattern: Pattern,
+    /// Period of oscillators and spaceships, `None` when unknown and 0 for
+    /// patterns that do not repeat.
+    pub period: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+impl CatalogEntry {
+
+    /// Name from the pattern metadata, empty if it has none.
+    pub fn name(&self) -> &str {
+        self.pattern.metadata().name.as_deref().unwrap_or("")
+    }
+
+    /// The entry as a JSON object, its cells left out.
+    pub fn to_json(&self, id: usize) -> String {
+        let tags: Vec<String> = self.tags.iter().map(|tag| json_string(tag)).collect();
+        format!(
+            "{{\"id\":{},\"name\":{},\"author\":{},\"width\":{},\"height\":{},\"population\":{},\"period\":{},\"tags\":[{}]}}",
+            id,
+            json_string(self.name()),
+            self.pattern.metadata().author.as_deref().map_or("null".to_string(), json_string),
+            self.pattern.width(),
+            self.pattern.height(),
+            self.pattern.population(),
+            self.period.map_or("null".to_string(), |period| period.to_string()),
+            tags.join(","),
+        )
+    }
+
+}
+
+/// What a search looks for, every field left to its default matches
+/// everything.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CatalogQuery {
+    /// Case insensitive substring of the name or comments.
+    pub text: String,
+    /// Tags the entries must all have.
+    pub tags: Vec<String>,
+    /// Largest bounding box.
+    pub max_size: Option<Size>,
+    pub period: Option<usize>,
+}
+
+/// Patterns indexed by name, size, period and tags.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct PatternCatalog {
+    entries: Vec<CatalogEntry>,
+    /// Lowercase name and comments of every entry.
+    text: Vec<String>,
+    by_tag: HashMap<String, Vec<usize>>,
+}
+
+impl PatternCatalog {
+
+    /// A catalog holding a few well known patterns.
+    pub fn builtin() -> Self {
+        let mut catalog = PatternCatalog::default();
+        for (name, period, tags, pattern) in BUILTIN.iter() {
+            let mut pattern = pattern.to_pattern();
+            pattern.metadata_mut().name = Some(name.to_string());
+            pattern.metadata_mut().rule = Some("B3/S23".to_string());
+            catalog.add(pattern, Some(*period), tags.iter().map(|tag| tag.to_string()).collect());
+        }
+        catalog
+    }
+
+    /// Add every `.rle` blob of a storage, untagged and of an unknown
+    /// period, and return their ids.
+    pub fn add_from_storage(&mut self, storage: &dyn Storage) -> Result<Vec<usize>, UniverseError> {
+        let mut ids = Vec::new();
+        for name in storage.list()?.iter().filter(|name| name.ends_with(".rle")) {
+            let data = storage.get(name)?.ok_or(UniverseError::Io("pattern removed from the storage"))?;
+            let text = String::from_utf8(data).map_err(|_| UniverseError::Io("pattern file is not UTF-8"))?;
+            ids.push(self.add(Pattern::from_rle(&text)?, None, Vec::new()));
+        }
+        Ok(ids)
+    }
+
+    /// Add a pattern and return its id.
+    pub fn add(&mut self, pattern: Pattern, period: Option<usize>, tags: Vec<String>) -> usize {
+        let id = self.entries.len();
+        let metadata = pattern.metadata();
+        let mut text = metadata.name.clone().unwrap_or_default();
+        for comment in &metadata.comments {
+            text.push('\n');
+            text += comment;
+        }
+        self.text.push(text.to_lowercase());
+        let tags: Vec<String> = tags.iter().map(|tag| tag.trim().to_lowercase()).filter(|tag| !tag.is_empty()).collect();
+        for tag in &tags {
+            self.by_tag.entry(tag.clone()).or_default().push(id);
+        }
+        self.entries.push(CatalogEntry { pattern, period, tags });
+        id
+    }
+
+    pub fn get(&self, id: usize) -> Option<&CatalogEntry> {
+        self.entries.get(id)
+    }
+
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Ids of the entries matching the query, in insertion order.
+    pub fn search(&self, query: &CatalogQuery) -> Vec<usize> {
+        let text = query.text.trim().to_lowercase();
+        let mut candidates: Vec<usize> = match query.tags.first() {
+            Some(tag) => self.by_tag.get(&tag.to_lowercase()).cloned().unwrap_or_default(),
+            None => (0..self.entries.len()).collect(),
+        };
+        candidates.retain(|&id| {
+            let entry = &self.entries[id];
+            let size = entry.pattern.size();
+            self.text[id].contains(&text)
+                && query.tags.iter().all(|tag| entry.tags.contains(&tag.to_lowercase()))
+                && query.max_size.is_none_or(|max| size.width <= max.width && size.height <= max.height)
+                && query.period.is_none_or(|period| entry.period == Some(period))
+        });
+        candidates
+    }
+
+}
+
+#[wasm_bindgen]
+impl PatternCatalog {
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        PatternCatalog::default()
+    }
+
+    /// Add an RLE pattern with comma separated tags, return its id.
+    #[wasm_bindgen(js_name = addRle)]
+    pub fn add_rle(&mut self, rle: &str, period: Option<u32>, tags: &str) -> Result<usize, JsValue> {
+        let pattern = Pattern::from_rle(rle).map_err(|error| JsValue::from_str(&error.to_string()))?;
+        Ok(self.add(pattern, period.map(|period| period as usize), tags.split(',').map(str::to_string).collect()))
+    }
+
+    /// Search by text and comma separated tags, return a JSON array of the
+    /// matching entries.
+    #[wasm_bindgen(js_name = searchJson)]
+    pub fn search_json(&self, text: &str, tags: &str) -> String {
+        let query = CatalogQuery {
+            text: text.to_string(),
+            tags: tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(str::to_string).collect(),
+            ..CatalogQuery::default()
+        };
+        let entries: Vec<String> = self.search(&query).into_iter().map(|id| self.entries[id].to_json(id)).collect();
+        format!("[{}]", entries.join(","))
+    }
+
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            c if (c as u32) < 0x20 => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CatalogQuery, PatternCatalog};
+    use crate::game_of_life::{MemoryStorage, Size, Storage};
+
+    #[test]
+    fn test_builtin_search() {
+        let catalog = PatternCatalog::builtin();
+        let names = |ids: Vec<usize>| -> Vec<&str> { ids.into_iter().map(|id| catalog.get(id).unwrap().name()).collect() };
+
+        let spaceships = CatalogQuery { tags: vec!["Spaceship".to_string()], ..CatalogQuery::default() };
+        assert_eq!(names(catalog.search(&spaceships)), vec!["Glider", "Lightweight spaceship"]);
+        let small = CatalogQuery { max_size: Some(Size::new(3, 3)), period: Some(4), ..CatalogQuery::default() };
+        assert_eq!(names(catalog.search(&small)), vec!["Glider"]);
+        let text = CatalogQuery { text: "GUN".to_string(), ..CatalogQuery::default() };
+        assert_eq!(names(catalog.search(&text)), vec!["Gosper glider gun"]);
+        assert_eq!(catalog.get(5).unwrap().pattern.population(), 36);
+    }
+
+    #[test]
+    fn test_json_search() {
+        let mut catalog = PatternCatalog::new();
+        let id = catalog.add_rle("#N Toad \"2\"\nx = 4, y = 2\nb3o$3o!", Some(2), "oscillator, period 2").unwrap();
+        assert_eq!(
+            catalog.search_json("toad", "oscillator"),
+            format!("[{{\"id\":{},\"name\":\"Toad \\\"2\\\"\",\"author\":null,\"width\":4,\"height\":2,\"population\":6,\"period\":2,\"tags\":[\"oscillator\",\"period 2\"]}}]", id)
+        );
+        assert_eq!(catalog.search_json("toad", "still life"), "[]");
+    }
+
+    #[test]
+    fn test_add_from_storage() {
+        let mut storage = MemoryStorage::new();
+        storage.put("toad.rle", b"#N Toad\nx = 4, y = 2\nb3o$3o!").unwrap();
+        storage.put("notes.txt", b"not a pattern").unwrap();
+        let mut catalog = PatternCatalog::new();
+        let ids = catalog.add_from_storage(&storage).unwrap();
+        assert_eq!(ids.len(), 1);
+        assert_eq!(catalog.get(ids[0]).unwrap().name(), "Toad");
+    }
+
+}
@@ -0,0 +1,141 @@
+use std::mem::size_of;
+
+use super::{BitFieldCompatible, BitFieldRepresentation, BitsField, BitsFieldError};
+
+/// A `BitsField` whose bits per element are known at compile time.
+///
+/// Word index, shift and mask of a value are computed from constants, so
+/// reads compile to a few instructions where a `BitsField` checks for
+/// values straddling words and shifts by its runtime width. `BITS` must
+/// divide the bits of a word, a field of 3 bits values does not compile.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitsFieldConst<T, const BITS: usize>
+where T: BitFieldRepresentation {
+    elements: Vec<T>,
+}
+
+impl<T, const BITS: usize> BitsFieldConst<T, BITS>
+where T: BitFieldRepresentation {
+
+    const WORD_BITS: usize = size_of::<T>() * 8;
+    const PER_WORD: usize = {
+        assert!(BITS > 0 && BITS <= size_of::<T>() * 8 && (size_of::<T>() * 8).is_multiple_of(BITS), "BITS must divide the bits of a word");
+        size_of::<T>() * 8 / BITS
+    };
+
+    pub fn new(size: usize) -> Result<Self, BitsFieldError> {
+        Self::from_field(BitsField::new(BITS, size)?)
+    }
+
+    /// The values of a field of `BITS` bits values.
+    pub fn from_field(field: BitsField<T>) -> Result<Self, BitsFieldError> {
+        if field.bits_per_element() != BITS {
+            return Err(BitsFieldError::ShapeMismatch);
+        }
+        // Evaluated here so an invalid `BITS` fails to compile.
+        let _ = Self::PER_WORD;
+        Ok(BitsFieldConst { elements: field.into_vec() })
+    }
+
+    pub fn into_field(self) -> BitsField<T> {
+        // The words came from a field of `BITS` bits values.
+        BitsField::from_raw_parts(BITS, &self.elements).unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len() * Self::PER_WORD
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    pub fn get<Output>(&self, index: usize) -> Result<Output, BitsFieldError>
+    where Output: BitFieldCompatible<T> {
+        if index >= self.len() {
+            return Err(BitsFieldError::OutOfBounds { index, len: self.len() });
+        }
+        // In bounds, just checked.
+        Ok(unsafe { self.get_unchecked(index) })
+    }
+
+    pub fn set<Output>(&mut self, index: usize, value: Output) -> Result<(), BitsFieldError>
+    where Output: BitFieldCompatible<T> {
+        if index >= self.len() {
+            return Err(BitsFieldError::OutOfBounds { index, len: self.len() });
+        }
+        unsafe { self.set_unchecked(index, value) };
+        Ok(())
+    }
+
+    /// # Safety
+    ///
+    /// `index` must be less than `len()`, which is only checked by debug
+    /// builds.
+    #[inline]
+    pub unsafe fn get_unchecked<Output>(&self, index: usize) -> Output
+    where Output: BitFieldCompatible<T> {
+        debug_assert!(index < self.len(), "index out of bounds");
+        let element = *self.elements.get_unchecked(index / Self::PER_WORD);
+        Output::from_type((element >> Self::shift(index)) & Self::mask())
+    }
+
+    /// # Safety
+    ///
+    /// `index` must be less than `len()`, which is only checked by debug
+    /// builds.
+    #[inline]
+    pub unsafe fn set_unchecked<Output>(&mut self, index: usize, value: Output)
+    where Output: BitFieldCompatible<T> {
+        debug_assert!(index < self.len(), "index out of bounds");
+        let shift = Self::shift(index);
+        let element = self.elements.get_unchecked_mut(index / Self::PER_WORD);
+        *element = (*element & !(Self::mask() << shift)) | ((value.to_type() & Self::mask()) << shift);
+    }
+
+    #[inline]
+    fn shift(index: usize) -> u8 {
+        ((index % Self::PER_WORD) * BITS) as u8
+    }
+
+    /// Folded to a constant once inlined.
+    #[inline]
+    fn mask() -> T {
+        if BITS == Self::WORD_BITS {
+            !T::from(0)
+        } else {
+            !(!T::from(0) << BITS as u8)
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitsFieldConst;
+    use crate::game_of_life::BitsField;
+
+    #[test]
+    fn test_matches_bits_field() {
+        let mut field = BitsField::<u32>::new(2, 50).unwrap();
+        field.update(|index, _: u8| (index % 4) as u8);
+        let mut fixed = BitsFieldConst::<u32, 2>::from_field(field.clone()).unwrap();
+        assert_eq!(fixed.len(), field.len());
+        assert!((0..field.len()).all(|index| fixed.get::<u8>(index).unwrap() == field.get::<u8>(index).unwrap()));
+
+        fixed.set(17, 3u8).unwrap();
+        field.set(17, 3u8).unwrap();
+        assert!(fixed.get::<u8>(fixed.len()).is_err());
+        assert_eq!(fixed.into_field(), field);
+        assert!(BitsFieldConst::<u32, 4>::from_field(field).is_err());
+
+        let mut full = BitsFieldConst::<u8, 8>::new(3).unwrap();
+        full.set(2, 255u8).unwrap();
+        assert_eq!(full.get::<u8>(2).unwrap(), 255);
+    }
+
+}
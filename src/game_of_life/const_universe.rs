@@ -0,0 +1,151 @@
+use super::StaticPattern;
+
+/// Birth and survival counts of Conway's Life, B3/S23.
+pub const LIFE: (u16, u16) = (1 << 3, 1 << 2 | 1 << 3);
+
+/// A two state universe of a size fixed at compile time, stored in arrays.
+///
+/// Everything is a `const fn` and only uses `core`, so tiny simulations run
+/// at compile time or on targets without a heap. Rules are life-like, given
+/// as birth and survival masks where bit `n` is set for `n` neighbors, and
+/// cells past the edges are dead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConstUniverse<const W: usize, const H: usize> {
+    cells: [[bool; W]; H],
+    generation: u64,
+}
+
+impl<const W: usize, const H: usize> ConstUniverse<W, H> {
+
+    /// A dead universe.
+    pub const fn new() -> Self {
+        ConstUniverse { cells: [[false; W]; H], generation: 0 }
+    }
+
+    pub const fn width(&self) -> usize {
+        W
+    }
+
+    pub const fn height(&self) -> usize {
+        H
+    }
+
+    /// Generations computed so far, stuck at `u64::MAX` past it.
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether the cell is alive, cells out of the grid are dead.
+    pub const fn get(&self, x: usize, y: usize) -> bool {
+        x < W && y < H && self.cells[y][x]
+    }
+
+    /// Set a cell, cells out of the grid are ignored.
+    pub const fn set(&mut self, x: usize, y: usize, alive: bool) {
+        if x < W && y < H {
+            self.cells[y][x] = alive;
+        }
+    }
+
+    /// Copy the live cells of a pattern, its top left corner at (x, y).
+    ///
+    /// Cells falling out of the grid are dropped.
+    pub const fn with_pattern(mut self, pattern: &StaticPattern, x: usize, y: usize) -> Self {
+        let mut i = 0;
+        while i < pattern.cells.len() {
+            let cell = pattern.cells[i];
+            self.set(x + cell.x as usize, y + cell.y as usize, true);
+            i += 1;
+        }
+        self
+    }
+
+    pub const fn population(&self) -> usize {
+        let mut population = 0;
+        let mut y = 0;
+        while y < H {
+            let mut x = 0;
+            while x < W {
+                population += self.cells[y][x] as usize;
+                x += 1;
+            }
+            y += 1;
+        }
+        population
+    }
+
+    /// The next generation under a life-like rule, see `LIFE`.
+    pub const fn step(&self, (birth, survival): (u16, u16)) -> Self {
+        let mut next = ConstUniverse { cells: [[false; W]; H], generation: self.generation.saturating_add(1) };
+        let mut y = 0;
+        while y < H {
+            let mut x = 0;
+            while x < W {
+                let neighbors = self.neighbors(x, y);
+                let mask = if self.cells[y][x] { survival } else { birth };
+                next.cells[y][x] = mask & (1 << neighbors) != 0;
+                x += 1;
+            }
+            y += 1;
+        }
+        next
+    }
+
+    /// Run `generations` generations.
+    pub const fn advance(&self, rule: (u16, u16), generations: u64) -> Self {
+        let mut universe = *self;
+        let mut i = 0;
+        while i < generations {
+            universe = universe.step(rule);
+            i += 1;
+        }
+        universe
+    }
+
+    const fn neighbors(&self, x: usize, y: usize) -> u32 {
+        let mut count = 0;
+        let mut dy = 0;
+        while dy < 3 {
+            let mut dx = 0;
+            while dx < 3 {
+                // Wrapping at 0 lands far out of the grid, where cells are dead.
+                let (nx, ny) = ((x + dx).wrapping_sub(1), (y + dy).wrapping_sub(1));
+                if !(dx == 1 && dy == 1) && self.get(nx, ny) {
+                    count += 1;
+                }
+                dx += 1;
+            }
+            dy += 1;
+        }
+        count
+    }
+
+}
+
+impl<const W: usize, const H: usize> Default for ConstUniverse<W, H> {
+    fn default() -> Self {
+        ConstUniverse::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstUniverse, LIFE};
+    use crate::pattern;
+
+    const GLIDER: ConstUniverse<6, 6> = ConstUniverse::new().with_pattern(&pattern!("x = 3, y = 3\nbo$2bo$3o!"), 0, 0);
+    const LATER: ConstUniverse<6, 6> = GLIDER.advance(LIFE, 4);
+
+    #[test]
+    fn test_glider_at_compile_time() {
+        const _: () = assert!(LATER.population() == 5 && LATER.generation() == 4);
+        let moved = ConstUniverse::<6, 6>::new().with_pattern(&pattern!("x = 3, y = 3\nbo$2bo$3o!"), 1, 1);
+        for y in 0..6 {
+            for x in 0..6 {
+                assert_eq!(LATER.get(x, y), moved.get(x, y));
+            }
+        }
+        assert!(!LATER.get(6, 0));
+    }
+
+}
@@ -0,0 +1,209 @@
+//! Errors of `BitsField`, `Universe` and the text parsers.
+//!
+//! Everything built on top of a universe, renderers, history, exports,
+//! storage and the like, reports a `UniverseError` too, so `?` works across
+//! the crate and callers can match on the kind of failure.
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BitsFieldError {
+    /// Values must take between 1 and `word_bits` bits, and divide them for
+    /// fields that can not straddle words.
+    InvalidBitsPerElement { bits: usize, word_bits: usize },
+    OutOfBounds { index: usize, len: usize },
+    /// Fields combined value by value must have the same bit width and
+    /// length.
+    ShapeMismatch,
+    /// More values than an `usize` can count.
+    SizeOverflow,
+    OutOfMemory,
+}
+
+impl BitsFieldError {
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            BitsFieldError::InvalidBitsPerElement { bits: 0, .. } => "bits_per_element must be greater than 0",
+            // Only fields setting whole words at once, like `AtomicBitsField`.
+            BitsFieldError::InvalidBitsPerElement { bits, word_bits } if bits <= word_bits => "bits_per_element must divide the bits of a word",
+            BitsFieldError::InvalidBitsPerElement { .. } => "bits_per_element must not be more than the bits of a word",
+            BitsFieldError::OutOfBounds { .. } => "index out of bounds",
+            BitsFieldError::ShapeMismatch => "fields must have the same bits per element and length",
+            BitsFieldError::SizeOverflow => "size overflows",
+            BitsFieldError::OutOfMemory => "not enough memory for the field",
+        }
+    }
+
+}
+
+impl fmt::Display for BitsFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitsFieldError::InvalidBitsPerElement { bits, word_bits } => write!(f, "{} ({} bits for words of {})", self.message(), bits, word_bits),
+            BitsFieldError::OutOfBounds { index, len } => write!(f, "index {} out of bounds of a field of {} values", index, len),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for BitsFieldError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UniverseError {
+    /// States must be between 2 and 256.
+    InvalidStates(usize),
+    /// The universe would take more than `memory_limit()`.
+    TooLarge,
+    OutOfBounds,
+    StateOutOfRange { state: u8, states: usize },
+    /// Cells, custom states or a pixel buffer not as long as the size they
+    /// go with asks for.
+    SizeMismatch { expected: usize, actual: usize },
+    /// Random initialization with a probability or a sigma out of range.
+    InvalidPolicy(&'static str),
+    RuleMismatch { rule_states: usize, states: usize },
+    PatternDoesNotFit,
+    /// `finish_step` without a `begin_step` first.
+    NoPendingStep,
+    /// A step past `u64::MAX` generations with `GenerationOverflow::Error`.
+    GenerationOverflow,
+    /// `History::record` of a universe not at the generation following the
+    /// history.
+    GenerationMismatch { expected: u64, actual: u64 },
+    /// A generation outside of the ones a `History` kept.
+    NotRecorded(u64),
+    /// An argument out of its range, with what is wrong with it.
+    InvalidArgument(&'static str),
+    /// A file or a storage could not be read or written.
+    Io(&'static str),
+    /// Stopped by a `CancelToken`.
+    Cancelled,
+    Field(BitsFieldError),
+    Parse(ParseError),
+}
+
+impl UniverseError {
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            UniverseError::InvalidStates(_) => "states must be between 2 and 256",
+            UniverseError::TooLarge => "universe too large for the memory limit, huge worlds need a sparse or chunked backend",
+            UniverseError::OutOfBounds => "coordinates out of bounds",
+            UniverseError::StateOutOfRange { .. } => "state out of range",
+            UniverseError::SizeMismatch { .. } => "length does not match the size",
+            UniverseError::InvalidPolicy(message) => message,
+            UniverseError::RuleMismatch { .. } => "rule and universe must have the same number of states",
+            UniverseError::PatternDoesNotFit => "pattern does not fit in the universe",
+            UniverseError::NoPendingStep => "no step begun, call begin_step first",
+            UniverseError::GenerationOverflow => "generation counter overflows",
+            UniverseError::GenerationMismatch { .. } => "universe is not at the generation following the history",
+            UniverseError::NotRecorded(_) => "generation was not recorded",
+            UniverseError::InvalidArgument(message) | UniverseError::Io(message) => message,
+            UniverseError::Cancelled => "operation cancelled",
+            UniverseError::Field(error) => error.message(),
+            UniverseError::Parse(error) => error.message,
+        }
+    }
+
+}
+
+impl fmt::Display for UniverseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UniverseError::InvalidStates(states) => write!(f, "{} (got {})", self.message(), states),
+            UniverseError::StateOutOfRange { state, states } => write!(f, "state {} out of range for {} states", state, states),
+            UniverseError::SizeMismatch { expected, actual } => write!(f, "{} ({} values for {})", self.message(), actual, expected),
+            UniverseError::RuleMismatch { rule_states, states } => write!(f, "{} ({} for {})", self.message(), rule_states, states),
+            UniverseError::GenerationMismatch { expected, actual } => write!(f, "{} (at {} instead of {})", self.message(), actual, expected),
+            UniverseError::NotRecorded(generation) => write!(f, "generation {} was not recorded", generation),
+            UniverseError::Field(error) => error.fmt(f),
+            UniverseError::Parse(error) => error.fmt(f),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl Error for UniverseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UniverseError::Field(error) => Some(error),
+            UniverseError::Parse(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<BitsFieldError> for UniverseError {
+    fn from(error: BitsFieldError) -> Self {
+        UniverseError::Field(error)
+    }
+}
+
+impl From<ParseError> for UniverseError {
+    fn from(error: ParseError) -> Self {
+        UniverseError::Parse(error)
+    }
+}
+
+/// What is wrong in a rulestring or an RLE file, and where.
+///
+/// Lines and columns count from 1, columns in characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: &'static str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ParseError {
+
+    /// An error at the character starting at byte `offset` of `line`.
+    pub(crate) fn at(message: &'static str, line_number: usize, line: &str, offset: usize) -> Self {
+        let column = line.get(..offset).map_or(line.chars().count(), |before| before.chars().count()) + 1;
+        ParseError { message, line: line_number, column }
+    }
+
+    /// An error past the last character of `text`, for input ending early.
+    pub(crate) fn end_of(text: &str, message: &'static str) -> Self {
+        let last = text.lines().last().unwrap_or("");
+        ParseError::at(message, text.lines().count().max(1), last, last.len())
+    }
+
+    /// The same error, in a text where the lines parsed come after line
+    /// `line`.
+    pub(crate) fn after_line(self, line: usize) -> Self {
+        ParseError { line: self.line + line, ..self }
+    }
+
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitsFieldError, ParseError, UniverseError};
+    use std::error::Error;
+
+    #[test]
+    fn test_messages() {
+        let error = UniverseError::from(BitsFieldError::OutOfBounds { index: 9, len: 8 });
+        assert_eq!(error.to_string(), "index 9 out of bounds of a field of 8 values");
+        assert!(error.source().is_some());
+        assert_eq!(UniverseError::InvalidStates(300).message(), "states must be between 2 and 256");
+        assert_eq!(ParseError::at("bad", 2, "αβ!", 4).to_string(), "bad at line 2, column 3");
+        let error = UniverseError::from(ParseError::at("bad", 1, "x", 0));
+        assert_eq!(error.message(), "bad");
+        assert!(error.source().is_some());
+    }
+
+}
@@ -0,0 +1,230 @@
+//! Image exports of large universes.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use ::image::RgbaImage;
+
+use super::{Point, Rect, Renderer, Size, Universe, UniverseError};
+
+/// Layout of an exported tile pyramid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TilePyramid {
+    /// Zoom levels, from 0 to `levels - 1`.
+    pub levels: u32,
+    pub tile_size: usize,
+    /// Size in pixels of the deepest level, one pixel per cell.
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Renderer {
+
+    /// Write the universe as a pyramid of PNG tiles, for slippy map viewers.
+    ///
+    /// Tiles are `directory/<z>/<x>/<y>.png`, `tile_size` pixels wide and
+    /// high except on the right and bottom edges. The deepest level has one
+    /// pixel per cell, every level above halves the size, down to level 0
+    /// which fits in a single tile. Shrunk pixels keep the highest state of
+    /// the cells they cover so thin lines do not vanish.
+    pub fn export_tiles(&self, universe: &Universe, directory: &Path, tile_size: usize) -> Result<TilePyramid, UniverseError> {
+        if tile_size == 0 {
+            return Err(UniverseError::InvalidArgument("tile_size must be greater than 0"));
+        }
+        let (mut width, mut height) = (universe.width(), universe.height());
+        let mut levels = 1;
+        while width.max(height) > tile_size << (levels - 1) {
+            levels += 1;
+        }
+        let pyramid = TilePyramid { levels, tile_size, width, height };
+
+        let mut states = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                states.push(universe.get_cell(x, y)?);
+            }
+        }
+        for level in (0..levels).rev() {
+            self.write_level(&states, width, height, &directory.join(level.to_string()), tile_size)?;
+            if level > 0 {
+                let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+                let mut half = vec![0; half_width * half_height];
+                for y in 0..height {
+                    for x in 0..width {
+                        let cell = &mut half[y / 2 * half_width + x / 2];
+                        *cell = (*cell).max(states[y * width + x]);
+                    }
+                }
+                states = half;
+                width = half_width;
+                height = half_height;
+            }
+        }
+        Ok(pyramid)
+    }
+
+    /// Write the cells of `rect` as a PNG image, one pixel per cell.
+    ///
+    /// The rectangle is clipped to the universe.
+    pub fn export_png_region(&self, universe: &Universe, rect: Rect, path: &Path) -> Result<(), UniverseError> {
+        let rect = rect.intersection(&universe.bounds()).ok_or(UniverseError::OutOfBounds)?;
+        let (left, top) = (rect.origin.x as usize, rect.origin.y as usize);
+        let mut pixels = Vec::with_capacity(rect.size.width * rect.size.height * 4);
+        for y in top..top + rect.size.height {
+            for x in left..left + rect.size.width {
+                let color = self.palette().color(universe.get_cell(x, y)?);
+                pixels.extend_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+        }
+        write_png(path, rect.size.width, rect.size.height, pixels)
+    }
+
+    fn write_level(&self, states: &[u8], width: usize, height: usize, directory: &Path, tile_size: usize) -> Result<(), UniverseError> {
+        for tile_x in 0..width.div_ceil(tile_size).max(1) {
+            let column = directory.join(tile_x.to_string());
+            fs::create_dir_all(&column).map_err(|_| UniverseError::Io("could not create the tile directory"))?;
+            for tile_y in 0..height.div_ceil(tile_size).max(1) {
+                let (left, top) = (tile_x * tile_size, tile_y * tile_size);
+                let (tile_width, tile_height) = (tile_size.min(width - left), tile_size.min(height - top));
+                let mut pixels = Vec::with_capacity(tile_width * tile_height * 4);
+                for y in top..top + tile_height {
+                    for &state in &states[y * width + left..y * width + left + tile_width] {
+                        let color = self.palette().color(state);
+                        pixels.extend_from_slice(&[color.r, color.g, color.b, 255]);
+                    }
+                }
+                write_png(&column.join(format!("{}.png", tile_y)), tile_width, tile_height, pixels)?;
+            }
+        }
+        Ok(())
+    }
+
+}
+
+/// Repeated exports of a universe as a grid of tiles, only writing again
+/// the tiles that changed since the previous export.
+///
+/// Tiles are `directory/<x>_<y>.png`. A tile is rewritten when its cells or
+/// the palette changed; files deleted behind the exporter's back are not
+/// noticed.
+#[derive(Clone, Debug)]
+pub struct TileExporter {
+    tile_size: usize,
+    fingerprints: HashMap<(usize, usize), u64>,
+}
+
+impl TileExporter {
+
+    pub fn new(tile_size: usize) -> Result<Self, UniverseError> {
+        if tile_size == 0 {
+            return Err(UniverseError::InvalidArgument("tile_size must be greater than 0"));
+        }
+        Ok(TileExporter { tile_size, fingerprints: HashMap::new() })
+    }
+
+    /// Forget what was written, the next export writes every tile.
+    pub fn invalidate(&mut self) {
+        self.fingerprints.clear();
+    }
+
+    /// Export the tiles that changed, and return their coordinates.
+    pub fn export(&mut self, renderer: &Renderer, universe: &Universe, directory: &Path) -> Result<Vec<(usize, usize)>, UniverseError> {
+        fs::create_dir_all(directory).map_err(|_| UniverseError::Io("could not create the tile directory"))?;
+        let size = self.tile_size;
+        let mut written = Vec::new();
+        for tile_y in 0..universe.height().div_ceil(size) {
+            for tile_x in 0..universe.width().div_ceil(size) {
+                let mut hasher = DefaultHasher::new();
+                renderer.palette().colors().hash(&mut hasher);
+                for y in tile_y * size..((tile_y + 1) * size).min(universe.height()) {
+                    for x in tile_x * size..((tile_x + 1) * size).min(universe.width()) {
+                        universe.get_cell(x, y)?.hash(&mut hasher);
+                    }
+                }
+                let fingerprint = hasher.finish();
+                if self.fingerprints.get(&(tile_x, tile_y)) == Some(&fingerprint) {
+                    continue;
+                }
+                let rect = Rect::new(Point::new((tile_x * size) as i64, (tile_y * size) as i64), Size::new(size, size));
+                renderer.export_png_region(universe, rect, &directory.join(format!("{}_{}.png", tile_x, tile_y)))?;
+                self.fingerprints.insert((tile_x, tile_y), fingerprint);
+                written.push((tile_x, tile_y));
+            }
+        }
+        Ok(written)
+    }
+
+}
+
+pub(crate) fn write_png(path: &Path, width: usize, height: usize, pixels: Vec<u8>) -> Result<(), UniverseError> {
+    let actual = pixels.len();
+    let image = RgbaImage::from_raw(width as u32, height as u32, pixels)
+        .ok_or(UniverseError::SizeMismatch { expected: width * height * 4, actual })?;
+    image.save(path).map_err(|_| UniverseError::Io("could not write the PNG file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ::image::GenericImageView;
+
+    use super::{TileExporter, TilePyramid};
+    use crate::game_of_life::{Color, Palette, Point, Rect, Renderer, Size, Universe};
+
+    fn scratch_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("game-of-life-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn test_tile_pyramid() {
+        let mut universe = Universe::new(10, 5).unwrap();
+        universe.set_cell(9, 4, 1).unwrap();
+        let directory = scratch_directory("tiles");
+        let renderer = Renderer::new(Palette::default_for(2));
+        let pyramid = renderer.export_tiles(&universe, &directory, 4).unwrap();
+        assert_eq!(pyramid, TilePyramid { levels: 3, tile_size: 4, width: 10, height: 5 });
+
+        // Deepest level: 3×2 tiles, the last one 2×1 pixels.
+        let corner = ::image::open(directory.join("2/2/1.png")).unwrap();
+        assert_eq!(corner.dimensions(), (2, 1));
+        assert_eq!(corner.get_pixel(1, 0).0, [255, 255, 255, 255]);
+        // Top level: the whole universe in a 3×2 tile, the live cell kept.
+        let top = ::image::open(directory.join("0/0/0.png")).unwrap();
+        assert_eq!(top.dimensions(), (3, 2));
+        assert_eq!(top.get_pixel(2, 1).0, [255, 255, 255, 255]);
+        assert!(!directory.join("0/1").exists());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_dirty_tiles() {
+        let mut universe = Universe::new(8, 6).unwrap();
+        let directory = scratch_directory("dirty");
+        let mut renderer = Renderer::new(Palette::default_for(2));
+        let mut exporter = TileExporter::new(4).unwrap();
+        assert_eq!(exporter.export(&renderer, &universe, &directory).unwrap().len(), 4);
+        assert!(exporter.export(&renderer, &universe, &directory).unwrap().is_empty());
+
+        universe.set_cell(5, 5, 1).unwrap();
+        assert_eq!(exporter.export(&renderer, &universe, &directory).unwrap(), vec![(1, 1)]);
+        let tile = ::image::open(directory.join("1_1.png")).unwrap();
+        assert_eq!(tile.dimensions(), (4, 2));
+        assert_eq!(tile.get_pixel(1, 1).0, [255, 255, 255, 255]);
+
+        renderer.set_palette(Palette::new(vec![Color::new(0, 0, 0), Color::new(255, 0, 0)]));
+        assert_eq!(exporter.export(&renderer, &universe, &directory).unwrap().len(), 4);
+
+        let region = directory.join("region.png");
+        renderer.export_png_region(&universe, Rect::new(Point::new(4, 4), Size::new(10, 10)), &region).unwrap();
+        assert_eq!(::image::open(&region).unwrap().dimensions(), (4, 2));
+        assert!(renderer.export_png_region(&universe, Rect::new(Point::new(-5, 0), Size::new(5, 5)), &region).is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+}
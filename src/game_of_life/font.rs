@@ -0,0 +1,166 @@
+use super::{Pattern, State, Universe, UniverseError};
+
+/// Width of a glyph of the embedded font, in cells.
+pub const GLYPH_WIDTH: usize = 5;
+/// Height of a glyph of the embedded font, in cells.
+pub const GLYPH_HEIGHT: usize = 7;
+
+/// Scale of the text written with the embedded 5×7 font.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontSize {
+    /// One cell per font pixel.
+    Small,
+    /// 2×2 cells per font pixel.
+    Medium,
+    /// 4×4 cells per font pixel.
+    Large,
+}
+
+impl FontSize {
+    pub fn scale(&self) -> usize {
+        match self {
+            FontSize::Small => 1,
+            FontSize::Medium => 2,
+            FontSize::Large => 4,
+        }
+    }
+}
+
+/// Rows of the glyph, top to bottom, the leftmost pixel being the highest
+/// of the 5 bits.
+///
+/// Lower case letters are drawn as upper case ones.
+fn glyph(c: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    Some(match c.to_ascii_uppercase() {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        ' ' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        '!' => [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100],
+        '?' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        '\'' => [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        '=' => [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        _ => return None,
+    })
+}
+
+impl Pattern {
+
+    /// Render text with the embedded 5×7 font.
+    ///
+    /// Glyphs are separated by one blank column and lines (split on `\n`)
+    /// by one blank row, both scaled with the font.
+    pub fn from_text(text: &str, size: FontSize) -> Result<Self, UniverseError> {
+        let scale = size.scale();
+        let lines: Vec<&str> = text.split('\n').collect();
+        let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+        let width = (columns * (GLYPH_WIDTH + 1)).saturating_sub(1) * scale;
+        let height = (lines.len() * (GLYPH_HEIGHT + 1) - 1) * scale;
+        let mut pattern = Pattern::new(width, height);
+
+        for (line_index, line) in lines.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let rows = glyph(c).ok_or(UniverseError::InvalidArgument("character not in the embedded font"))?;
+                let left = column * (GLYPH_WIDTH + 1) * scale;
+                let top = line_index * (GLYPH_HEIGHT + 1) * scale;
+                for (gy, row) in rows.iter().enumerate() {
+                    for gx in 0..GLYPH_WIDTH {
+                        if row & (1 << (GLYPH_WIDTH - 1 - gx)) == 0 {
+                            continue;
+                        }
+                        for sy in 0..scale {
+                            for sx in 0..scale {
+                                pattern.set(left + gx * scale + sx, top + gy * scale + sy, State::Alive)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(pattern)
+    }
+
+}
+
+impl Universe {
+
+    /// Write text in live cells, its top left corner at (x, y).
+    pub fn stamp_text(&mut self, text: &str, x: usize, y: usize, size: FontSize) -> Result<(), UniverseError> {
+        let pattern = Pattern::from_text(text, size)?;
+        self.stamp(&pattern, x, y)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FontSize, Pattern, Universe};
+
+    #[test]
+    fn test_text_size() {
+        let pattern = Pattern::from_text("HI", FontSize::Small).unwrap();
+        assert_eq!(pattern.width(), 11);
+        assert_eq!(pattern.height(), 7);
+        // "I" middle column.
+        assert_eq!(pattern.get(8, 3).unwrap(), 1);
+        // Spacing column.
+        assert_eq!(pattern.get(5, 3).unwrap(), 0);
+
+        let pattern = Pattern::from_text("hi\nyo", FontSize::Medium).unwrap();
+        assert_eq!(pattern.width(), 22);
+        assert_eq!(pattern.height(), 30);
+    }
+
+    #[test]
+    fn test_stamp_text() {
+        let mut universe = Universe::new(32, 16).unwrap();
+        universe.stamp_text("HELLO", 1, 1, FontSize::Small).unwrap();
+        // Left bar of the "H".
+        assert!((1..8).all(|y| universe.is_alive(1, y).unwrap()));
+        assert!(universe.stamp_text("\u{263A}", 0, 0, FontSize::Small).is_err());
+        assert!(universe.stamp_text("HELLO", 0, 0, FontSize::Medium).is_err());
+    }
+
+}
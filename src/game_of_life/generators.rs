@@ -0,0 +1,220 @@
+//! Obstacle generators.
+//!
+//! Every generator writes into the frozen mask of the universe, leaving the
+//! live cells that do not end up under a wall untouched.
+
+use super::{Rng, Universe, UniverseError, Xoshiro256};
+
+/// Carve a perfect maze with the recursive backtracker algorithm.
+///
+/// Walls are one cell thick and corridors `corridor` cells wide, so patterns
+/// have some room to travel. Cells on the right and bottom that do not fit a
+/// whole maze cell are walled too.
+pub fn maze(universe: &mut Universe, corridor: usize, seed: u64) -> Result<(), UniverseError> {
+    if corridor == 0 {
+        return Err(UniverseError::InvalidArgument("corridor must be at least one cell wide"));
+    }
+    let step = corridor + 1;
+    let columns = universe.width().saturating_sub(1) / step;
+    let rows = universe.height().saturating_sub(1) / step;
+    if columns == 0 || rows == 0 {
+        return Err(UniverseError::InvalidArgument("universe is too small for the maze"));
+    }
+
+    for y in 0..universe.height() {
+        for x in 0..universe.width() {
+            universe.set_frozen(x, y, true)?;
+        }
+    }
+    let carve = |universe: &mut Universe, left: usize, top: usize, width: usize, height: usize| {
+        for y in top..top + height {
+            for x in left..left + width {
+                universe.set_frozen(x, y, false)?;
+            }
+        }
+        Ok::<(), UniverseError>(())
+    };
+
+    let mut rng = Xoshiro256::new(seed);
+    let mut visited = vec![false; columns * rows];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    carve(universe, 1, 1, corridor, corridor)?;
+
+    while let Some(&(column, row)) = stack.last() {
+        let mut candidates = Vec::with_capacity(4);
+        if column > 0 && !visited[row * columns + column - 1] {
+            candidates.push((column - 1, row));
+        }
+        if column + 1 < columns && !visited[row * columns + column + 1] {
+            candidates.push((column + 1, row));
+        }
+        if row > 0 && !visited[(row - 1) * columns + column] {
+            candidates.push((column, row - 1));
+        }
+        if row + 1 < rows && !visited[(row + 1) * columns + column] {
+            candidates.push((column, row + 1));
+        }
+        if candidates.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let (next_column, next_row) = candidates[rng.below(candidates.len())];
+        visited[next_row * columns + next_column] = true;
+        // Open the next cell and the wall between both cells.
+        let left = column.min(next_column) * step + 1;
+        let top = row.min(next_row) * step + 1;
+        if next_row == row {
+            carve(universe, left, top, corridor * 2 + 1, corridor)?;
+        } else {
+            carve(universe, left, top, corridor, corridor * 2 + 1)?;
+        }
+        stack.push((next_column, next_row));
+    }
+    Ok(())
+}
+
+/// Drop `count` randomly placed solid rectangles, sides between `min_size`
+/// and `max_size` cells.
+pub fn random_rectangles(universe: &mut Universe, count: usize, min_size: usize, max_size: usize, seed: u64) -> Result<(), UniverseError> {
+    if min_size == 0 || min_size > max_size {
+        return Err(UniverseError::InvalidArgument("rectangle sizes must satisfy 0 < min_size <= max_size"));
+    }
+    if max_size > universe.width() || max_size > universe.height() {
+        return Err(UniverseError::InvalidArgument("rectangles must fit in the universe"));
+    }
+    let mut rng = Xoshiro256::new(seed);
+    for _ in 0..count {
+        let width = min_size + rng.below(max_size - min_size + 1);
+        let height = min_size + rng.below(max_size - min_size + 1);
+        let left = rng.below(universe.width() - width + 1);
+        let top = rng.below(universe.height() - height + 1);
+        for y in top..top + height {
+            for x in left..left + width {
+                universe.set_frozen(x, y, true)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Cave like obstacles, walls where Perlin noise goes above `threshold`.
+///
+/// `scale` is the size of a noise feature in cells, `threshold` lies in
+/// `-1.0..1.0`: the higher it is, the more open the caves.
+pub fn perlin_caves(universe: &mut Universe, scale: f64, threshold: f64, seed: u64) -> Result<(), UniverseError> {
+    if scale.is_nan() || scale <= 0.0 {
+        return Err(UniverseError::InvalidArgument("scale must be a positive number"));
+    }
+    let noise = Perlin::new(seed);
+    for y in 0..universe.height() {
+        for x in 0..universe.width() {
+            if noise.at(x as f64 / scale, y as f64 / scale) > threshold {
+                universe.set_frozen(x, y, true)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Classic 2D gradient noise over a seeded permutation table.
+struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    fn new(seed: u64) -> Self {
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        let mut rng = Xoshiro256::new(seed);
+        for i in (1..table.len()).rev() {
+            table.swap(i, rng.below(i + 1));
+        }
+        let mut permutation = [0u8; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = table[i % 256];
+        }
+        Perlin { permutation }
+    }
+
+    fn gradient(hash: u8, x: f64, y: f64) -> f64 {
+        match hash & 3 {
+            0 => x + y,
+            1 => -x + y,
+            2 => x - y,
+            _ => -x - y,
+        }
+    }
+
+    fn at(&self, x: f64, y: f64) -> f64 {
+        let fade = |t: f64| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+
+        let (xi, yi) = (x.floor() as usize & 255, y.floor() as usize & 255);
+        let (xf, yf) = (x - x.floor(), y - y.floor());
+        let (u, v) = (fade(xf), fade(yf));
+        let p = &self.permutation;
+        let hash = |dx: usize, dy: usize| p[p[xi + dx] as usize + yi + dy];
+
+        let bottom = lerp(u, Self::gradient(hash(0, 0), xf, yf), Self::gradient(hash(1, 0), xf - 1.0, yf));
+        let top = lerp(u, Self::gradient(hash(0, 1), xf, yf - 1.0), Self::gradient(hash(1, 1), xf - 1.0, yf - 1.0));
+        // The gradients above can reach ±2, bring the result back to ±1.
+        lerp(v, bottom, top) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{maze, perlin_caves, random_rectangles};
+    use crate::game_of_life::Universe;
+
+    fn frozen_count(universe: &Universe) -> usize {
+        (0..universe.height())
+            .flat_map(|y| (0..universe.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| universe.is_frozen(x, y).unwrap())
+            .count()
+    }
+
+    #[test]
+    fn test_maze_is_connected() {
+        let mut universe = Universe::new(21, 21).unwrap();
+        maze(&mut universe, 1, 42).unwrap();
+
+        // Flood fill from the first corridor cell must reach every open cell.
+        let mut seen = vec![false; 21 * 21];
+        let mut stack = vec![(1usize, 1usize)];
+        seen[22] = true;
+        while let Some((x, y)) = stack.pop() {
+            for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if !seen[ny * 21 + nx] && !universe.is_frozen(nx, ny).unwrap() {
+                    seen[ny * 21 + nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        let open = 21 * 21 - frozen_count(&universe);
+        assert_eq!(seen.iter().filter(|&&s| s).count(), open);
+        // A perfect maze on a 10x10 grid has 100 cells and 99 openings.
+        assert_eq!(open, 100 + 99);
+    }
+
+    #[test]
+    fn test_generators_are_reproducible() {
+        let mut first = Universe::new(40, 30).unwrap();
+        let mut second = Universe::new(40, 30).unwrap();
+        random_rectangles(&mut first, 5, 2, 6, 7).unwrap();
+        random_rectangles(&mut second, 5, 2, 6, 7).unwrap();
+        perlin_caves(&mut first, 8.0, 0.1, 7).unwrap();
+        perlin_caves(&mut second, 8.0, 0.1, 7).unwrap();
+        for y in 0..30 {
+            for x in 0..40 {
+                assert_eq!(first.is_frozen(x, y).unwrap(), second.is_frozen(x, y).unwrap());
+            }
+        }
+        assert!(frozen_count(&first) > 0);
+    }
+
+}
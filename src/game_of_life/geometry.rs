@@ -0,0 +1,193 @@
+use std::convert::TryFrom;
+use std::ops::{Add, Neg, Sub};
+
+/// A cell position.
+///
+/// Coordinates are signed so positions left or above the origin of an
+/// unbounded universe can be expressed, grids start at (0, 0).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+
+    pub const ORIGIN: Point = Point::new(0, 0);
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Point { x, y }
+    }
+
+    /// Position of a cell of a grid, `None` if it does not fit in an `i64`.
+    pub fn from_unsigned(x: usize, y: usize) -> Option<Self> {
+        Some(Point::new(i64::try_from(x).ok()?, i64::try_from(y).ok()?))
+    }
+
+    /// Grid coordinates, `None` for negative positions.
+    pub fn to_unsigned(self) -> Option<(usize, usize)> {
+        Some((usize::try_from(self.x).ok()?, usize::try_from(self.y).ok()?))
+    }
+
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+/// Width and height of a rectangle of cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Size {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Size {
+
+    pub const fn new(width: usize, height: usize) -> Self {
+        Size { width, height }
+    }
+
+    /// Number of cells, `None` on overflow.
+    pub fn area(&self) -> Option<usize> {
+        self.width.checked_mul(self.height)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+}
+
+/// A rectangle of cells, from its top left corner `origin` included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub origin: Point,
+    pub size: Size,
+}
+
+impl Rect {
+
+    pub const fn new(origin: Point, size: Size) -> Self {
+        Rect { origin, size }
+    }
+
+    /// The smallest rectangle holding both corners.
+    pub fn from_corners(a: Point, b: Point) -> Self {
+        let origin = Point::new(a.x.min(b.x), a.y.min(b.y));
+        let size = Size::new((a.x - b.x).unsigned_abs() as usize + 1, (a.y - b.y).unsigned_abs() as usize + 1);
+        Rect { origin, size }
+    }
+
+    /// Bottom right corner, excluded.
+    pub fn end(&self) -> Point {
+        Point::new(self.origin.x + self.size.width as i64, self.origin.y + self.size.height as i64)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size.is_empty()
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let end = self.end();
+        (self.origin.x..end.x).contains(&point.x) && (self.origin.y..end.y).contains(&point.y)
+    }
+
+    /// Whether `other` lies wholly inside this rectangle.
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.is_empty() || {
+            let (end, other_end) = (self.end(), other.end());
+            other.origin.x >= self.origin.x
+                && other.origin.y >= self.origin.y
+                && other_end.x <= end.x
+                && other_end.y <= end.y
+        }
+    }
+
+    /// Cells in both rectangles, `None` when they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let (end, other_end) = (self.end(), other.end());
+        let origin = Point::new(self.origin.x.max(other.origin.x), self.origin.y.max(other.origin.y));
+        let corner = Point::new(end.x.min(other_end.x), end.y.min(other_end.y));
+        if corner.x <= origin.x || corner.y <= origin.y {
+            None
+        } else {
+            Some(Rect::new(origin, Size::new((corner.x - origin.x) as usize, (corner.y - origin.y) as usize)))
+        }
+    }
+
+    /// The smallest rectangle holding both, empty rectangles are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let (end, other_end) = (self.end(), other.end());
+        let origin = Point::new(self.origin.x.min(other.origin.x), self.origin.y.min(other.origin.y));
+        let corner = Point::new(end.x.max(other_end.x), end.y.max(other_end.y));
+        Rect::new(origin, Size::new((corner.x - origin.x) as usize, (corner.y - origin.y) as usize))
+    }
+
+    pub fn translate(&self, offset: Point) -> Rect {
+        Rect::new(self.origin + offset, self.size)
+    }
+
+    /// Every cell of the rectangle, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (origin, end) = (self.origin, self.end());
+        (origin.y..end.y).flat_map(move |y| (origin.x..end.x).map(move |x| Point::new(x, y)))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Point, Rect, Size};
+
+    #[test]
+    fn test_rect_operations() {
+        let a = Rect::new(Point::new(-2, -2), Size::new(4, 4));
+        let b = Rect::from_corners(Point::new(3, 3), Point::new(1, 0));
+        assert_eq!(b, Rect::new(Point::new(1, 0), Size::new(3, 4)));
+
+        assert!(a.contains(Point::new(-2, 1)));
+        assert!(!a.contains(Point::new(2, 0)));
+        assert_eq!(a.intersection(&b), Some(Rect::new(Point::new(1, 0), Size::new(1, 2))));
+        assert_eq!(a.union(&b), Rect::new(Point::new(-2, -2), Size::new(6, 6)));
+        assert!(a.union(&b).contains_rect(&b));
+        assert_eq!(a.intersection(&a.translate(Point::new(4, 0))), None);
+        assert_eq!(b.points().count(), 12);
+        assert_eq!(b.points().next(), Some(b.origin));
+    }
+
+    #[test]
+    fn test_point_conversions() {
+        assert_eq!(Point::from_unsigned(3, 4).unwrap().to_unsigned(), Some((3, 4)));
+        assert_eq!(Point::new(-1, 0).to_unsigned(), None);
+        assert_eq!(Point::new(1, 2) - Point::new(3, 3), -Point::new(2, 1));
+    }
+
+}
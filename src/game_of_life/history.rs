@@ -0,0 +1,176 @@
+use super::{Universe, UniverseError};
+
+/// Cells that changed between two generations, as `(index, state)` with
+/// cells numbered row by row.
+type Diff = Vec<(usize, u8)>;
+
+/// The recorded run of a universe, to go back to any generation.
+///
+/// A full copy of the universe is kept every `keyframe_interval`
+/// generations and only the changed cells in between, so seeking replays
+/// at most `keyframe_interval - 1` diffs.
+pub struct History {
+    start: u64,
+    keyframe_interval: u64,
+    keyframes: Vec<Universe>,
+    /// `diffs[i]` goes from generation `start + i` to the next one.
+    diffs: Vec<Diff>,
+    last: Universe,
+}
+
+/// A seek done a few diffs at a time, see `History::start_seek`.
+pub struct Seek {
+    target: u64,
+    universe: Universe,
+}
+
+impl Seek {
+
+    /// The generation reached so far.
+    pub fn generation(&self) -> u64 {
+        self.universe.generation()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.universe.generation() == self.target
+    }
+
+    /// The universe at the generation reached so far.
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+
+    pub fn into_universe(self) -> Universe {
+        self.universe
+    }
+
+}
+
+impl History {
+
+    /// Start recording from the current generation of `universe`.
+    pub fn new(universe: &Universe, keyframe_interval: u64) -> Result<Self, UniverseError> {
+        if keyframe_interval == 0 {
+            return Err(UniverseError::InvalidArgument("keyframe_interval must be greater than 0"));
+        }
+        Ok(History {
+            start: universe.generation(),
+            keyframe_interval,
+            keyframes: vec![universe.clone()],
+            diffs: Vec::new(),
+            last: universe.clone(),
+        })
+    }
+
+    /// First recorded generation.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Last recorded generation.
+    pub fn end(&self) -> u64 {
+        self.start + self.diffs.len() as u64
+    }
+
+    /// Record the next generation, call it after every step.
+    pub fn record(&mut self, universe: &Universe) -> Result<(), UniverseError> {
+        let expected = self.end().checked_add(1).ok_or(UniverseError::GenerationOverflow)?;
+        if universe.generation() != expected {
+            return Err(UniverseError::GenerationMismatch { expected, actual: universe.generation() });
+        }
+        if universe.width() != self.last.width() || universe.height() != self.last.height() {
+            return Err(UniverseError::InvalidArgument("universe size changed"));
+        }
+        let width = universe.width();
+        let mut diff = Diff::new();
+        for y in 0..universe.height() {
+            for x in 0..width {
+                let state = universe.get_cell(x, y)?;
+                if state != self.last.get_cell(x, y)? {
+                    diff.push((y * width + x, state));
+                }
+            }
+        }
+        self.diffs.push(diff);
+        self.last = universe.clone();
+        if (self.diffs.len() as u64).is_multiple_of(self.keyframe_interval) {
+            self.keyframes.push(universe.clone());
+        }
+        Ok(())
+    }
+
+    /// The universe as it was at `generation`.
+    pub fn seek(&self, generation: u64) -> Result<Universe, UniverseError> {
+        let mut seek = self.start_seek(generation)?;
+        self.continue_seek(&mut seek, usize::MAX)?;
+        Ok(seek.into_universe())
+    }
+
+    /// Start a seek from the closest keyframe, without replaying anything.
+    ///
+    /// Call `continue_seek` until it is done, for instance once a frame in
+    /// a browser so the page stays responsive.
+    pub fn start_seek(&self, generation: u64) -> Result<Seek, UniverseError> {
+        if generation < self.start || generation > self.end() {
+            return Err(UniverseError::NotRecorded(generation));
+        }
+        let keyframe = ((generation - self.start) / self.keyframe_interval) as usize;
+        Ok(Seek { target: generation, universe: self.keyframes[keyframe].clone() })
+    }
+
+    /// Replay at most `budget` diffs of a seek, return whether it is done.
+    pub fn continue_seek(&self, seek: &mut Seek, budget: usize) -> Result<bool, UniverseError> {
+        let width = seek.universe.width();
+        for _ in 0..budget {
+            if seek.is_done() {
+                break;
+            }
+            let generation = seek.universe.generation();
+            for &(index, state) in &self.diffs[(generation - self.start) as usize] {
+                seek.universe.write_cell(index % width, index / width, state)?;
+            }
+            seek.universe.set_generation(generation + 1);
+        }
+        Ok(seek.is_done())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::game_of_life::{rules, PatternCatalog, Universe, UniverseError};
+
+    #[test]
+    fn test_seek() {
+        let life = rules::parse("B3/S23").unwrap();
+        let glider = PatternCatalog::builtin().entries().iter().find(|entry| entry.name() == "Glider").unwrap().pattern.clone();
+        let mut universe = Universe::new(12, 12).unwrap();
+        universe.stamp(&glider, 0, 0).unwrap();
+
+        let mut history = History::new(&universe, 4).unwrap();
+        let mut expected = vec![universe.clone()];
+        for _ in 0..10 {
+            universe.step(life.as_ref()).unwrap();
+            history.record(&universe).unwrap();
+            expected.push(universe.clone());
+        }
+        assert_eq!(history.end(), 10);
+        let cells = |universe: &Universe| -> Vec<u8> { (0..144).map(|i| universe.get_cell(i % 12, i / 12).unwrap()).collect() };
+        for (generation, universe) in expected.iter().enumerate() {
+            let seeked = history.seek(generation as u64).unwrap();
+            assert_eq!(seeked.generation(), generation as u64);
+            assert_eq!(cells(&seeked), cells(universe));
+        }
+        assert!(matches!(history.seek(11), Err(UniverseError::NotRecorded(11))));
+        assert_eq!(history.record(&universe).err(), Some(UniverseError::GenerationMismatch { expected: 11, actual: 10 }));
+
+        // From keyframe 8, one diff at a time.
+        let mut seek = history.start_seek(10).unwrap();
+        assert_eq!(seek.generation(), 8);
+        assert!(!history.continue_seek(&mut seek, 1).unwrap());
+        assert!(history.continue_seek(&mut seek, 1).unwrap());
+        assert_eq!(cells(seek.universe()), cells(&expected[10]));
+    }
+
+}
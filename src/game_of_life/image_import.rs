@@ -0,0 +1,96 @@
+use ::image::DynamicImage;
+
+use super::{State, Universe, UniverseError};
+
+/// How the grey levels of an image are turned into live and dead cells.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageConversion {
+    /// Pixels darker than the threshold are alive.
+    Threshold(u8),
+    /// Floyd–Steinberg error diffusion, keeps the overall shading of photos.
+    Dither,
+}
+
+impl Universe {
+
+    /// Build a universe the size of the image, dark pixels becoming live cells.
+    ///
+    /// Transparent pixels are blended over white, so they end up dead.
+    pub fn from_image(img: &DynamicImage, conversion: ImageConversion) -> Result<Self, UniverseError> {
+        let img = img.to_luma_alpha8();
+        let (width, height) = (img.width() as usize, img.height() as usize);
+
+        let mut luma: Vec<f32> = img.pixels()
+            .map(|pixel| {
+                let [l, a] = pixel.0;
+                let alpha = a as f32 / 255.0;
+                l as f32 * alpha + 255.0 * (1.0 - alpha)
+            })
+            .collect();
+
+        let mut universe = Universe::new(width, height)?;
+        for y in 0..height {
+            for x in 0..width {
+                let value = luma[y * width + x];
+                let (alive, error) = match conversion {
+                    ImageConversion::Threshold(threshold) => (value < threshold as f32, 0.0),
+                    ImageConversion::Dither => {
+                        let alive = value < 128.0;
+                        (alive, value - if alive { 0.0 } else { 255.0 })
+                    }
+                };
+                if alive {
+                    universe.set_cell(x, y, State::Alive)?;
+                }
+                if error != 0.0 {
+                    // Spread the quantization error on the pixels not yet visited.
+                    let mut spread = |dx: isize, dy: usize, weight: f32| {
+                        let nx = x as isize + dx;
+                        let ny = y + dy;
+                        if nx >= 0 && (nx as usize) < width && ny < height {
+                            luma[ny * width + nx as usize] += error * weight;
+                        }
+                    };
+                    spread(1, 0, 7.0 / 16.0);
+                    spread(-1, 1, 3.0 / 16.0);
+                    spread(0, 1, 5.0 / 16.0);
+                    spread(1, 1, 1.0 / 16.0);
+                }
+            }
+        }
+        Ok(universe)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use ::image::{DynamicImage, GrayImage, Luma};
+
+    use super::{ImageConversion, Universe};
+
+    fn gradient() -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(16, 16, |x, _| Luma([(x * 16) as u8])))
+    }
+
+    #[test]
+    fn test_threshold() {
+        let universe = Universe::from_image(&gradient(), ImageConversion::Threshold(128)).unwrap();
+        assert_eq!(universe.width(), 16);
+        for x in 0..16 {
+            assert_eq!(universe.is_alive(x, 3).unwrap(), x < 8);
+        }
+    }
+
+    #[test]
+    fn test_dither_keeps_shading() {
+        let universe = Universe::from_image(&gradient(), ImageConversion::Dither).unwrap();
+        let alive_in_column = |x| (0..16).filter(|&y| universe.is_alive(x, y).unwrap()).count();
+        // Dark columns are mostly alive, light ones mostly dead, and the
+        // middle is a mix rather than a hard edge.
+        assert!(alive_in_column(0) > 12);
+        assert!(alive_in_column(15) < 4);
+        assert!((4..12).contains(&alive_in_column(8)));
+    }
+
+}
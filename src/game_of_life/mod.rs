@@ -1,8 +1,64 @@
 pub mod universe;
+pub mod analysis;
 pub mod state;
 pub mod rules;
+pub mod atomic_bitsfield;
 pub mod bitsfield;
+pub mod cancel;
+pub mod catalog;
+pub mod const_bitsfield;
+pub mod const_universe;
+pub mod error;
+pub mod geometry;
+pub mod pattern;
+pub mod rle;
+pub mod search;
+pub mod storage;
+pub mod font;
+pub mod generators;
+pub mod history;
+pub mod palette;
+pub mod prelude;
+pub mod progress;
+pub mod random;
+pub mod symmetry;
+pub mod render;
+pub mod topology;
+pub mod warmup;
+#[cfg(feature = "bot")]
+pub mod bot;
+#[cfg(feature = "image")]
+pub mod export;
+#[cfg(feature = "image")]
+pub mod image_import;
+#[cfg(feature = "qrcode")]
+pub mod qr;
 
-pub use universe::Universe;
+pub use universe::{memory_limit, set_memory_limit, ClipMode, GenerationOverflow, InitPolicy, PlacementReport, Transition, Universe};
 pub use state::State;
-pub use bitsfield::{BitsField, BitFieldCompatible, BitFieldRepresentation};
\ No newline at end of file
+pub use bitsfield::{AutoBitsField, BitLayout, BitsField, BitsFieldView, RowView, RowViewMut, BitFieldCompatible, BitFieldRepresentation};
+#[cfg(feature = "derive")]
+pub use wasm_game_of_life_derive::BitFieldCompatible;
+pub use atomic_bitsfield::{AtomicBitsField, AtomicWord};
+pub use const_bitsfield::BitsFieldConst;
+pub use cancel::CancelToken;
+pub use catalog::{CatalogEntry, CatalogQuery, PatternCatalog};
+pub use const_universe::ConstUniverse;
+pub use error::{BitsFieldError, ParseError, UniverseError};
+pub use geometry::{Point, Rect, Size};
+pub use pattern::{Metadata, Pattern, StaticPattern};
+pub use font::FontSize;
+pub use history::{History, Seek};
+pub use rules::{Rule, RuleConfig, TableRule, TreeRule};
+pub use palette::{Color, Palette};
+pub use progress::{NoProgress, Progress, ProgressCallback};
+pub use random::{Rng, Xoshiro256};
+pub use render::{Renderer, RgbaBuffer, TextRenderer};
+pub use storage::{DirectoryStorage, MemoryStorage, Storage};
+pub use symmetry::Symmetry;
+pub use topology::EdgeBehavior;
+pub use warmup::{Warmup, WarmupStop};
+#[cfg(feature = "image")]
+pub use export::{TileExporter, TilePyramid};
+#[cfg(feature = "image")]
+pub use image_import::ImageConversion;
\ No newline at end of file
@@ -0,0 +1,166 @@
+use super::{ParseError, UniverseError};
+use super::rules::golly;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    /// Linear interpolation, `t` going from 0 (self) to 1 (other).
+    pub fn lerp(&self, other: Color, t: f64) -> Color {
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
+
+}
+
+/// The color of every state of a rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<Color>,
+}
+
+impl Palette {
+
+    pub fn new(colors: Vec<Color>) -> Self {
+        Palette { colors }
+    }
+
+    /// Golly's default colors: dark gray dead cells, and live states going
+    /// from red to yellow, or white when there is only one.
+    pub fn default_for(states: usize) -> Self {
+        let mut colors = vec![Color::new(48, 48, 48)];
+        if states == 2 {
+            colors.push(Color::new(255, 255, 255));
+        } else {
+            colors.extend(Palette::gradient(Color::new(255, 0, 0), Color::new(255, 255, 0), states.saturating_sub(1)));
+        }
+        Palette { colors }
+    }
+
+    /// `count` colors evenly spread from `start` to `end`, both included.
+    pub fn gradient(start: Color, end: Color, count: usize) -> Vec<Color> {
+        (0..count)
+            .map(|i| match count {
+                1 => start,
+                _ => start.lerp(end, i as f64 / (count - 1) as f64),
+            })
+            .collect()
+    }
+
+    /// Palette for rules with decay states: black dead cells, `alive` live
+    /// ones and the decay states fading from `start` to `end`.
+    pub fn fade(states: usize, alive: Color, start: Color, end: Color) -> Self {
+        let mut colors = vec![Color::new(0, 0, 0), alive];
+        colors.extend(Palette::gradient(start, end, states.saturating_sub(2)));
+        colors.truncate(states.max(1));
+        Palette { colors }
+    }
+
+    /// Read the `@COLORS` section of a Golly `.rule` file, states it does not
+    /// mention keep their default color.
+    ///
+    /// Lines are either `state r g b`, or `r1 g1 b1 r2 g2 b2` for a gradient
+    /// over every live state. `@ICONS` sections are not read.
+    pub fn from_rule_file(text: &str, states: usize) -> Result<Self, ParseError> {
+        let mut palette = Palette::default_for(states);
+        let section = match golly::section(text, "COLORS") {
+            Some(section) => section,
+            None => return Ok(palette),
+        };
+
+        let mut read_line = |line: &str| -> Result<(), &'static str> {
+            if line.is_empty() {
+                return Ok(());
+            }
+            let numbers = line
+                .split_whitespace()
+                .map(|number| number.parse::<u8>().map_err(|_| "invalid number in @COLORS"))
+                .collect::<Result<Vec<_>, _>>()?;
+            match numbers[..] {
+                [state, r, g, b] => palette.set(state, Color::new(r, g, b)).map_err(|error| error.message())?,
+                [r1, g1, b1, r2, g2, b2] => {
+                    let live = Palette::gradient(Color::new(r1, g1, b1), Color::new(r2, g2, b2), states.saturating_sub(1));
+                    palette.colors.truncate(1);
+                    palette.colors.extend(live);
+                }
+                _ => return Err("@COLORS lines need 4 or 6 numbers"),
+            }
+            Ok(())
+        };
+        for (index, line) in section.lines.iter().enumerate() {
+            read_line(golly::strip_comment(line))
+                .map_err(|message| golly::line_error(&section.lines, index, message).after_line(section.line))?;
+        }
+        Ok(palette)
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Color of a state, states past the end of the palette use its last color.
+    pub fn color(&self, state: u8) -> Color {
+        let index = (state as usize).min(self.colors.len().saturating_sub(1));
+        self.colors.get(index).copied().unwrap_or(Color::new(0, 0, 0))
+    }
+
+    pub fn set(&mut self, state: u8, color: Color) -> Result<(), UniverseError> {
+        let states = self.colors.len();
+        match self.colors.get_mut(state as usize) {
+            Some(slot) => {
+                *slot = color;
+                Ok(())
+            }
+            None => Err(UniverseError::StateOutOfRange { state, states }),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Palette};
+
+    #[test]
+    fn test_colors_section() {
+        let text = "
+@RULE Demo
+@COLORS
+0 0 0 0       # dead
+2 0 0 255
+";
+        let palette = Palette::from_rule_file(text, 3).unwrap();
+        assert_eq!(palette.colors(), &[Color::new(0, 0, 0), Color::new(255, 0, 0), Color::new(0, 0, 255)]);
+        let error = Palette::from_rule_file("@RULE x\n@COLORS\n\n  3 1 2 3", 3).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 3));
+    }
+
+    #[test]
+    fn test_colors_gradient() {
+        let palette = Palette::from_rule_file("@COLORS\n0 0 255 255 0 0", 4).unwrap();
+        assert_eq!(palette.len(), 4);
+        assert_eq!(palette.color(1), Color::new(0, 0, 255));
+        assert_eq!(palette.color(2), Color::new(128, 0, 128));
+        assert_eq!(palette.color(3), Color::new(255, 0, 0));
+        // No section, default colors.
+        assert_eq!(Palette::from_rule_file("@RULE x", 2).unwrap(), Palette::default_for(2));
+    }
+
+}
@@ -0,0 +1,210 @@
+use super::rules::{self, Rule};
+use super::{ParseError, Point, Rect, Size, State, UniverseError};
+
+/// A pattern is a small rectangular block of cells that can be stamped
+/// into a universe.
+///
+/// Cells are stored row by row as raw state values, `0` being dead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+    metadata: Metadata,
+}
+
+/// What pattern files tell about a pattern besides its cells.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    /// Rulestring the pattern was made for, like `B3/S23`.
+    pub rule: Option<String>,
+}
+
+impl Pattern {
+
+    /// Create an empty (all dead) pattern.
+    pub fn new(width: usize, height: usize) -> Self {
+        Pattern {
+            width,
+            height,
+            cells: vec![State::Dead.into(); width * height],
+            metadata: Metadata::default(),
+        }
+    }
+
+    /// Create a pattern from row-major cell states.
+    pub fn from_cells(width: usize, height: usize, cells: Vec<u8>) -> Result<Self, UniverseError> {
+        if width.checked_mul(height) != Some(cells.len()) {
+            Err(UniverseError::SizeMismatch { expected: width.saturating_mul(height), actual: cells.len() })
+        } else {
+            Ok(Pattern { width, height, cells, metadata: Metadata::default() })
+        }
+    }
+
+    /// Convert pasted ASCII (or emoji) art into a pattern.
+    ///
+    /// Every character found in `alive_chars` becomes a live cell, anything
+    /// else is dead. Each line is a row and each character a column, rows
+    /// shorter than the longest one are padded with dead cells. Blank lines
+    /// around the art and the common indentation are dropped so indented
+    /// string literals work as is.
+    pub fn from_ascii_art(art: &str, alive_chars: &str) -> Self {
+        let rows: Vec<Vec<char>> = art
+            .lines()
+            // Variation selectors only change how the previous emoji is
+            // drawn, they are not cells on their own.
+            .map(|line| line.trim_end().chars().filter(|c| !matches!(c, '\u{FE0E}' | '\u{FE0F}')).collect())
+            .collect();
+
+        let first = rows.iter().position(|row| !row.is_empty()).unwrap_or(rows.len());
+        let last = rows.iter().rposition(|row| !row.is_empty()).map_or(first, |i| i + 1);
+        let rows = &rows[first..last];
+        let indent = rows.iter()
+            .filter(|row| !row.is_empty())
+            .map(|row| row.iter().take_while(|c| c.is_whitespace() && !alive_chars.contains(**c)).count())
+            .min()
+            .unwrap_or(0);
+        let rows: Vec<&[char]> = rows.iter().map(|row| row.get(indent..).unwrap_or(&[])).collect();
+
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut pattern = Pattern::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if alive_chars.contains(*c) {
+                    pattern.cells[y * width + x] = State::Alive.into();
+                }
+            }
+        }
+        pattern
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// The smallest rectangle holding every non dead cell, `None` if there
+    /// are none.
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let mut live = (0..self.cells.len())
+            .filter(|&i| self.cells[i] != 0)
+            .map(|i| Rect::new(Point::new((i % self.width) as i64, (i / self.width) as i64), Size::new(1, 1)));
+        let first = live.next()?;
+        Some(live.fold(first, |bounds, cell| bounds.union(&cell)))
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Metadata {
+        &mut self.metadata
+    }
+
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    pub(crate) fn cells_mut(&mut self) -> &mut [u8] {
+        &mut self.cells
+    }
+
+    /// The rule the metadata asks for, if any.
+    ///
+    /// Loaders switch the simulation to it unless the user asked to keep
+    /// the current rule.
+    pub fn rule_hint(&self) -> Option<Result<Box<dyn Rule>, ParseError>> {
+        self.metadata.rule.as_deref().map(rules::parse)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Result<u8, UniverseError> {
+        if x >= self.width || y >= self.height {
+            Err(UniverseError::OutOfBounds)
+        } else {
+            Ok(self.cells[y * self.width + x])
+        }
+    }
+
+    pub fn set<S>(&mut self, x: usize, y: usize, state: S) -> Result<(), UniverseError>
+    where S: Into<u8> {
+        if x >= self.width || y >= self.height {
+            Err(UniverseError::OutOfBounds)
+        } else {
+            self.cells[y * self.width + x] = state.into();
+            Ok(())
+        }
+    }
+
+    /// Number of non dead cells.
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&cell| cell != 0).count()
+    }
+
+}
+
+/// A two state pattern decoded at compile time by the `pattern!` macro.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticPattern {
+    pub size: Size,
+    /// Coordinates of the live cells.
+    pub cells: &'static [Point],
+}
+
+impl StaticPattern {
+
+    pub fn to_pattern(&self) -> Pattern {
+        let mut pattern = Pattern::new(self.size.width, self.size.height);
+        for cell in self.cells {
+            // The macro checked every cell is inside the pattern.
+            pattern.cells[cell.y as usize * self.size.width + cell.x as usize] = State::Alive.into();
+        }
+        pattern
+    }
+
+}
+
+impl From<&StaticPattern> for Pattern {
+    fn from(pattern: &StaticPattern) -> Pattern {
+        pattern.to_pattern()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+    use crate::game_of_life::{Point, Rect, Size};
+
+    #[test]
+    fn test_from_ascii_art() {
+        let pattern = Pattern::from_ascii_art("
+            .#.
+            ..#
+            ###
+        ", "#");
+        assert_eq!(pattern.height(), 3);
+        assert_eq!(pattern.width(), 3);
+        assert_eq!(pattern.population(), 5);
+        assert_eq!(pattern.cells(), &[0, 1, 0, 0, 0, 1, 1, 1, 1]);
+        assert_eq!(pattern.bounding_box(), Some(Rect::new(Point::new(0, 0), Size::new(3, 3))));
+        assert_eq!(Pattern::new(2, 2).bounding_box(), None);
+    }
+
+    #[test]
+    fn test_from_emoji_art() {
+        let pattern = Pattern::from_ascii_art("⬛🟩⬛\n🟩\u{FE0F}🟩", "🟩");
+        assert_eq!(pattern.width(), 3);
+        assert_eq!(pattern.height(), 2);
+        assert_eq!(pattern.cells(), &[0, 1, 0, 1, 1, 0]);
+    }
+
+}
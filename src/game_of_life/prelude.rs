@@ -0,0 +1,6 @@
+//! The types most programs need, `use wasm_game_of_life::game_of_life::prelude::*;`
+//! to get them all.
+
+pub use super::rules::{self, Rule};
+pub use super::{BitsFieldError, ParseError, UniverseError};
+pub use super::{InitPolicy, Pattern, State, Universe};
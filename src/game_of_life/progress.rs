@@ -0,0 +1,67 @@
+use wasm_bindgen::prelude::*;
+
+/// Receives the advancement of a long operation, to draw a progress bar.
+///
+/// `fraction` goes from 0.0 to 1.0, `message` tells what is being done.
+/// Closures taking `(f64, &str)` are progress reporters.
+pub trait Progress {
+    fn report(&mut self, fraction: f64, message: &str);
+
+    /// Report `done` steps out of `total`.
+    fn report_steps(&mut self, done: usize, total: usize, message: &str) {
+        let fraction = if total == 0 { 1.0 } else { done as f64 / total as f64 };
+        self.report(fraction.min(1.0), message);
+    }
+}
+
+impl<F> Progress for F
+where F: FnMut(f64, &str) {
+    fn report(&mut self, fraction: f64, message: &str) {
+        self(fraction, message)
+    }
+}
+
+/// Drops every report.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn report(&mut self, _fraction: f64, _message: &str) {}
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// A JavaScript `(fraction, message) => void` function.
+    pub type ProgressCallback;
+
+    #[wasm_bindgen(method, js_name = call)]
+    fn call_with(this: &ProgressCallback, context: &JsValue, fraction: f64, message: &str);
+}
+
+impl Progress for ProgressCallback {
+    fn report(&mut self, fraction: f64, message: &str) {
+        self.call_with(&JsValue::NULL, fraction, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Progress;
+
+    #[test]
+    fn test_closure_progress() {
+        let mut reports = Vec::new();
+        {
+            let mut progress = |fraction: f64, message: &str| reports.push((fraction, message.to_string()));
+            progress.report_steps(1, 4, "loading");
+            progress.report_steps(5, 4, "loading");
+            progress.report_steps(0, 0, "done");
+        }
+        assert_eq!(reports, vec![
+            (0.25, "loading".to_string()),
+            (1.0, "loading".to_string()),
+            (1.0, "done".to_string()),
+        ]);
+    }
+
+}
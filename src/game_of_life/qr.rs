@@ -0,0 +1,65 @@
+use ::qrcode::{Color, QrCode};
+
+use super::{Pattern, State, Universe, UniverseError};
+
+impl Pattern {
+
+    /// Encode text as a QR code, dark modules being live cells.
+    ///
+    /// Each module is drawn as a `module_size`×`module_size` square of cells.
+    pub fn from_qr(text: &str, module_size: usize) -> Result<Self, UniverseError> {
+        if module_size == 0 {
+            return Err(UniverseError::InvalidArgument("module_size must be greater than 0"));
+        }
+        let code = QrCode::new(text.as_bytes()).map_err(|_| UniverseError::InvalidArgument("text can not be encoded as a QR code"))?;
+        let modules = code.width();
+        let colors = code.to_colors();
+
+        let size = modules * module_size;
+        let mut pattern = Pattern::new(size, size);
+        for y in 0..size {
+            for x in 0..size {
+                if colors[(y / module_size) * modules + x / module_size] == Color::Dark {
+                    pattern.set(x, y, State::Alive)?;
+                }
+            }
+        }
+        Ok(pattern)
+    }
+
+}
+
+impl Universe {
+
+    /// Stamp the QR code of `text` centered in the universe.
+    pub fn seed_qr(&mut self, text: &str, module_size: usize) -> Result<(), UniverseError> {
+        let pattern = Pattern::from_qr(text, module_size)?;
+        if pattern.width() > self.width() || pattern.height() > self.height() {
+            return Err(UniverseError::PatternDoesNotFit);
+        }
+        let x = (self.width() - pattern.width()) / 2;
+        let y = (self.height() - pattern.height()) / 2;
+        self.stamp(&pattern, x, y)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pattern, Universe, UniverseError};
+
+    #[test]
+    fn test_seed_qr() {
+        // Version 1 codes are 21 modules wide.
+        let pattern = Pattern::from_qr("life", 2).unwrap();
+        assert_eq!(pattern.width(), 42);
+
+        let mut universe = Universe::new(50, 50).unwrap();
+        universe.seed_qr("life", 2).unwrap();
+        // Top left finder pattern corner, offset by the centering margin.
+        assert!(universe.is_alive(4, 4).unwrap());
+        assert!(!universe.is_alive(3, 3).unwrap());
+        assert_eq!(universe.seed_qr("life", 3), Err(UniverseError::PatternDoesNotFit));
+    }
+
+}
@@ -0,0 +1,111 @@
+//! Seedable random numbers.
+//!
+//! Everything random in the crate draws from an `Rng` built from a `u64`
+//! seed, the same seed always gives the same results on every platform.
+
+/// Source of random numbers.
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform value in `0..bound`, `bound` must not be zero.
+    fn below(&mut self, bound: usize) -> usize {
+        let bound = bound as u64;
+        // Reject the top values that would make the low ones more likely.
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % bound) as usize;
+            }
+        }
+    }
+
+    /// Uniform value in `0.0..1.0`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// True with probability `probability`.
+    fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+/// The default generator, xoshiro256**.
+///
+/// It keeps the seed it was built from so runs can record it and be
+/// replayed.
+#[derive(Clone, Debug)]
+pub struct Xoshiro256 {
+    seed: u64,
+    state: [u64; 4],
+}
+
+impl Xoshiro256 {
+
+    pub fn new(seed: u64) -> Self {
+        // Spread the seed with splitmix64, as recommended by the authors, so
+        // close seeds give unrelated streams and the state is never zero.
+        let mut mix = seed;
+        let mut state = [0; 4];
+        for word in state.iter_mut() {
+            mix = mix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = mix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *word = z ^ (z >> 31);
+        }
+        Xoshiro256 { seed, state }
+    }
+
+    /// The seed this generator was built from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+}
+
+impl Rng for Xoshiro256 {
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rng, Xoshiro256};
+
+    #[test]
+    fn test_reproducible_streams() {
+        let mut first = Xoshiro256::new(7);
+        let mut second = Xoshiro256::new(7);
+        let values: Vec<u64> = (0..8).map(|_| first.next_u64()).collect();
+        assert!(values.iter().all(|&value| value == second.next_u64()));
+        assert_ne!(Xoshiro256::new(8).next_u64(), values[0]);
+        assert_eq!(first.seed(), 7);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Xoshiro256::new(1);
+        let mut seen = [0usize; 6];
+        for _ in 0..6000 {
+            seen[rng.below(6)] += 1;
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+        assert!(seen.iter().all(|&count| (800..1200).contains(&count)));
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+    }
+
+}
@@ -0,0 +1,347 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::{Palette, Pattern, Rule, Transition, Universe, UniverseError};
+
+/// An RGBA image, pixels row by row, 4 bytes each.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+/// Fails unless `buffer` holds 4 bytes per cell of `universe`.
+pub(crate) fn check_rgba_buffer(universe: &Universe, buffer: &[u8]) -> Result<(), UniverseError> {
+    let expected = universe.width() * universe.height() * 4;
+    if buffer.len() != expected {
+        return Err(UniverseError::SizeMismatch { expected, actual: buffer.len() });
+    }
+    Ok(())
+}
+
+/// Paints a universe into an RGBA buffer, one pixel per cell.
+pub struct Renderer {
+    palette: Palette,
+}
+
+impl Renderer {
+
+    pub fn new(palette: Palette) -> Self {
+        Renderer { palette }
+    }
+
+    /// Use the colors the rule asks for, Generations rules get their decay
+    /// states faded for instance.
+    pub fn for_rule(rule: &dyn Rule) -> Self {
+        Renderer::new(rule.palette())
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    /// Pixels row by row, 4 bytes each.
+    pub fn render_rgba(&self, universe: &Universe) -> Vec<u8> {
+        let mut buffer = vec![0; universe.width() * universe.height() * 4];
+        // The buffer is sized for the universe, this can not fail.
+        self.render_rgba_into(universe, &mut buffer).unwrap();
+        buffer
+    }
+
+    /// Same as `render_rgba`, reusing a buffer of the right size.
+    pub fn render_rgba_into(&self, universe: &Universe, buffer: &mut [u8]) -> Result<(), UniverseError> {
+        check_rgba_buffer(universe, buffer)?;
+        for y in 0..universe.height() {
+            self.render_row(universe, y, buffer)?;
+        }
+        Ok(())
+    }
+
+    /// Repaint only the dirty rows of the universe into a buffer holding
+    /// its previous frame, return the spans of rows that were redrawn.
+    ///
+    /// Dirty rows are left as they are, clear them once the spans are
+    /// copied to the canvas.
+    pub fn render_dirty_rgba_into(&self, universe: &Universe, buffer: &mut [u8]) -> Result<Vec<Range<usize>>, UniverseError> {
+        check_rgba_buffer(universe, buffer)?;
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        for (y, _) in universe.dirty_rows().iter().enumerate().filter(|(_, &dirty)| dirty) {
+            self.render_row(universe, y, buffer)?;
+            match spans.last_mut() {
+                Some(span) if span.end == y => span.end += 1,
+                _ => spans.push(y..y + 1),
+            }
+        }
+        Ok(spans)
+    }
+
+    /// Paint the frame `t` of the way (0 to 1) from the previous generation
+    /// to the current one: born cells fade in from the dead color, dying
+    /// cells fade out to it.
+    ///
+    /// Lets slow generation rates animate smoothly at the display refresh
+    /// rate.
+    pub fn render_transition_rgba_into(&self, universe: &Universe, t: f64, buffer: &mut [u8]) -> Result<(), UniverseError> {
+        check_rgba_buffer(universe, buffer)?;
+        let t = if t.is_nan() { 1.0 } else { t.clamp(0.0, 1.0) };
+        let dead = self.palette.color(0);
+        for y in 0..universe.height() {
+            for x in 0..universe.width() {
+                let color = match universe.transition(x, y)? {
+                    Transition::Born => dead.lerp(self.palette.color(universe.get_cell(x, y)?), t),
+                    Transition::Died => self.palette.color(universe.previous_cell(x, y)?).lerp(dead, t),
+                    _ => self.palette.color(universe.get_cell(x, y)?),
+                };
+                let offset = (y * universe.width() + x) * 4;
+                buffer[offset..offset + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+        }
+        Ok(())
+    }
+
+    fn render_row(&self, universe: &Universe, y: usize, buffer: &mut [u8]) -> Result<(), UniverseError> {
+        for x in 0..universe.width() {
+            let color = self.palette.color(universe.get_cell(x, y)?);
+            let offset = (y * universe.width() + x) * 4;
+            buffer[offset..offset + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+        }
+        Ok(())
+    }
+
+}
+
+/// Paints a universe as text, one symbol per cell, for terminals and chat
+/// bots.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextRenderer {
+    /// Symbol of every state, states past the end use the last one.
+    symbols: Vec<String>,
+    /// Colors of the symbols as 24 bits ANSI escapes, none by default.
+    palette: Option<Palette>,
+    max_width: Option<usize>,
+}
+
+impl TextRenderer {
+
+    pub fn new(symbols: Vec<String>) -> Result<Self, UniverseError> {
+        if symbols.len() < 2 {
+            return Err(UniverseError::InvalidArgument("text rendering needs a symbol for dead and for live cells"));
+        }
+        Ok(TextRenderer { symbols, palette: None, max_width: None })
+    }
+
+    /// Black and white squares, which line up in chat apps.
+    pub fn emoji() -> Self {
+        TextRenderer { symbols: vec!["⬛".to_string(), "⬜".to_string()], palette: None, max_width: None }
+    }
+
+    pub fn ascii() -> Self {
+        TextRenderer { symbols: vec![".".to_string(), "#".to_string()], palette: None, max_width: None }
+    }
+
+    /// Color every symbol with its state color, `None` for plain text.
+    pub fn with_ansi(mut self, palette: Option<Palette>) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Most symbols per line. Wider universes are shrunk, each symbol
+    /// standing for a square of cells and showing their highest state.
+    pub fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Rows of symbols, each ended by a newline.
+    pub fn render(&self, universe: &Universe) -> Result<String, UniverseError> {
+        let cells = match self.max_width {
+            Some(0) => return Err(UniverseError::InvalidArgument("max_width must be greater than 0")),
+            Some(max_width) => universe.width().div_ceil(max_width).max(1),
+            None => 1,
+        };
+        let mut text = String::new();
+        for y in (0..universe.height()).step_by(cells) {
+            let mut color = None;
+            for x in (0..universe.width()).step_by(cells) {
+                let mut state = 0;
+                for dy in 0..cells.min(universe.height() - y) {
+                    for dx in 0..cells.min(universe.width() - x) {
+                        state = state.max(universe.get_cell(x + dx, y + dy)?);
+                    }
+                }
+                if let Some(palette) = &self.palette {
+                    let wanted = palette.color(state);
+                    // Escapes only where the color changes keep messages short.
+                    if color != Some(wanted) {
+                        let _ = write!(text, "\x1b[38;2;{};{};{}m", wanted.r, wanted.g, wanted.b);
+                        color = Some(wanted);
+                    }
+                }
+                text += &self.symbols[(state as usize).min(self.symbols.len() - 1)];
+            }
+            if color.is_some() {
+                text += "\x1b[0m";
+            }
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+}
+
+impl Pattern {
+
+    /// A preview of the pattern after `generations` generations of `rule`,
+    /// at most `max_px` pixels wide and high.
+    ///
+    /// The preview is cropped to the live cells. Small patterns get square
+    /// pixels of several cells, large ones merge blocks of cells keeping
+    /// their highest state so thin lines stay visible.
+    pub fn thumbnail(&self, max_px: usize, generations: usize, rule: &dyn Rule) -> Result<RgbaBuffer, UniverseError> {
+        if max_px == 0 {
+            return Err(UniverseError::InvalidArgument("max_px must be greater than 0"));
+        }
+        // Nothing moves faster than one cell a generation, this margin is
+        // enough for the pattern to never reach the edges.
+        let margin = generations;
+        let mut universe = Universe::with_states(self.width() + 2 * margin, self.height() + 2 * margin, rule.states())?;
+        universe.stamp(self, margin, margin)?;
+        for _ in 0..generations {
+            universe.step(rule)?;
+        }
+
+        let mut live = (0..universe.height())
+            .flat_map(|y| (0..universe.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| universe.get_cell(x, y).unwrap_or(0) != 0);
+        let (left, top, right, bottom) = match live.next() {
+            Some((x, y)) => live.fold((x, y, x, y), |(left, top, right, bottom), (x, y)| {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }),
+            None => (margin, margin, margin + self.width().max(1) - 1, margin + self.height().max(1) - 1),
+        };
+        let (cells_wide, cells_high) = (right - left + 1, bottom - top + 1);
+        let longest = cells_wide.max(cells_high);
+        // Either `pixels` pixels per cell, or `cells` cells per pixel.
+        let (pixels, cells) = if longest <= max_px { (max_px / longest, 1) } else { (1, longest.div_ceil(max_px)) };
+        let width = cells_wide.div_ceil(cells) * pixels;
+        let height = cells_high.div_ceil(cells) * pixels;
+
+        let palette = rule.palette();
+        let mut buffer = RgbaBuffer { width, height, pixels: vec![0; width * height * 4] };
+        for py in 0..height {
+            for px in 0..width {
+                let (x, y) = (left + px / pixels * cells, top + py / pixels * cells);
+                let mut state = 0;
+                for dy in 0..cells.min(bottom + 1 - y) {
+                    for dx in 0..cells.min(right + 1 - x) {
+                        state = state.max(universe.get_cell(x + dx, y + dy)?);
+                    }
+                }
+                let color = palette.color(state);
+                let offset = (py * width + px) * 4;
+                buffer.pixels[offset..offset + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+        }
+        Ok(buffer)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Renderer, TextRenderer};
+    use crate::game_of_life::rules::{self, Generations};
+    use crate::game_of_life::{Color, Palette, Pattern, Transition, Universe};
+
+    #[test]
+    fn test_generations_fade() {
+        let rule = Generations::parse("B2/S345/C6").unwrap();
+        let renderer = Renderer::for_rule(&rule);
+        let palette = renderer.palette();
+        assert_eq!(palette.len(), 6);
+        assert_eq!(palette.color(1), Color::new(255, 255, 255));
+        assert_eq!(palette.color(2), Color::new(255, 220, 0));
+        assert_eq!(palette.color(5), Color::new(80, 0, 0));
+
+        let mut universe = Universe::with_states(3, 1, 6).unwrap();
+        universe.set_cell(1, 0, 1).unwrap();
+        universe.set_cell(2, 0, 5).unwrap();
+        assert_eq!(renderer.render_rgba(&universe), vec![
+            0, 0, 0, 255,
+            255, 255, 255, 255,
+            80, 0, 0, 255,
+        ]);
+    }
+
+    #[test]
+    fn test_dirty_rows() {
+        let life = rules::parse("B3/S23").unwrap();
+        let renderer = Renderer::for_rule(life.as_ref());
+        let mut universe = Universe::new(5, 7).unwrap();
+        universe.stamp(&Pattern::from_ascii_art("OOO", "O"), 1, 4).unwrap();
+        let mut buffer = vec![0; 5 * 7 * 4];
+        assert_eq!(renderer.render_dirty_rgba_into(&universe, &mut buffer).unwrap(), vec![0..7]);
+        universe.clear_dirty_rows();
+
+        // The blinker turns over rows 3 to 5.
+        universe.step(life.as_ref()).unwrap();
+        assert_eq!(universe.dirty_rows(), &[false, false, false, true, true, true, false]);
+        assert_eq!(renderer.render_dirty_rgba_into(&universe, &mut buffer).unwrap(), vec![3..6]);
+        assert_eq!(buffer, renderer.render_rgba(&universe));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let life = rules::parse("B3/S23").unwrap();
+        let renderer = Renderer::for_rule(life.as_ref());
+        let mut universe = Universe::new(5, 7).unwrap();
+        universe.stamp(&Pattern::from_ascii_art("OOO", "O"), 1, 4).unwrap();
+        let before = renderer.render_rgba(&universe);
+        universe.step(life.as_ref()).unwrap();
+        assert_eq!(universe.transition(2, 3).unwrap(), Transition::Born);
+        assert_eq!(universe.transition(1, 4).unwrap(), Transition::Died);
+        assert_eq!(universe.transition(2, 4).unwrap(), Transition::Unchanged);
+
+        let mut buffer = vec![0; 5 * 7 * 4];
+        renderer.render_transition_rgba_into(&universe, 0.0, &mut buffer).unwrap();
+        assert_eq!(buffer, before);
+        renderer.render_transition_rgba_into(&universe, 1.0, &mut buffer).unwrap();
+        assert_eq!(buffer, renderer.render_rgba(&universe));
+    }
+
+    #[test]
+    fn test_text() {
+        let mut universe = Universe::new(4, 2).unwrap();
+        universe.set_cell(1, 0, 1).unwrap();
+        assert_eq!(TextRenderer::ascii().render(&universe).unwrap(), ".#..\n....\n");
+        assert_eq!(TextRenderer::emoji().with_max_width(Some(2)).render(&universe).unwrap(), "⬜⬛\n");
+
+        let palette = Palette::new(vec![Color::new(0, 0, 0), Color::new(255, 0, 0)]);
+        let colored = TextRenderer::new(vec![" ".to_string(), "o".to_string()]).unwrap().with_ansi(Some(palette));
+        assert_eq!(colored.render(&universe).unwrap().lines().next().unwrap(), "\x1b[38;2;0;0;0m \x1b[38;2;255;0;0mo\x1b[38;2;0;0;0m  \x1b[0m");
+        assert!(TextRenderer::new(vec!["x".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        let life = rules::parse("B3/S23").unwrap();
+        let blinker = Pattern::from_ascii_art("OOO", "O");
+        let still = blinker.thumbnail(12, 0, life.as_ref()).unwrap();
+        assert_eq!((still.width, still.height), (12, 4));
+        let turned = blinker.thumbnail(12, 1, life.as_ref()).unwrap();
+        assert_eq!((turned.width, turned.height), (4, 12));
+        assert_eq!(&turned.pixels[..4], &[255, 255, 255, 255]);
+
+        // Ten cells wide in four pixels, three cells a pixel.
+        let line = Pattern::from_ascii_art("O.O.O.O.O.", "O");
+        let small = line.thumbnail(4, 0, life.as_ref()).unwrap();
+        assert_eq!((small.width, small.height), (3, 1));
+        assert!(small.pixels.chunks(4).all(|pixel| pixel == [255, 255, 255, 255]));
+    }
+
+}
@@ -0,0 +1,511 @@
+//! Run Length Encoded patterns.
+//!
+//! The `const fn` parser lets the `pattern!` macro decode patterns while
+//! compiling. It only knows two state patterns: `b` (or `.`) for dead cells,
+//! `o` for live ones, `$` for the end of a row and `!` for the end of the
+//! pattern, each optionally preceded by a run count.
+//!
+//! `Pattern::from_rle` parses at runtime and also reads Golly's multi-state
+//! cells (`A` to `X`, prefixed by `p` to `y` past state 24) and the `#N`,
+//! `#O` and `#C` metadata lines.
+
+use super::universe::memory_limit;
+use super::{Metadata, ParseError, Pattern, Point};
+
+/// Longest line `Pattern::to_rle` writes, as in Golly.
+const LINE_LENGTH: usize = 70;
+
+/// Parse an RLE pattern at compile time into a `StaticPattern`.
+///
+/// Malformed patterns are compile errors:
+///
+/// ```
+/// use wasm_game_of_life::pattern;
+/// use wasm_game_of_life::game_of_life::StaticPattern;
+///
+/// static GLIDER: StaticPattern = pattern!("x = 3, y = 3\nbob$2bo$3o!");
+/// assert_eq!(GLIDER.cells.len(), 5);
+/// ```
+///
+/// ```compile_fail
+/// use wasm_game_of_life::pattern;
+/// use wasm_game_of_life::game_of_life::StaticPattern;
+///
+/// // Four cells on a row of three.
+/// static BROKEN: StaticPattern = pattern!("x = 3, y = 1\n4o!");
+/// ```
+#[macro_export]
+macro_rules! pattern {
+    ($rle:expr) => {{
+        const RLE: &str = $rle;
+        const SIZE: (usize, usize) = match $crate::game_of_life::rle::const_size(RLE) {
+            Ok(size) => size,
+            Err(error) => panic!("{}", error),
+        };
+        const COUNT: usize = match $crate::game_of_life::rle::const_live_count(RLE) {
+            Ok(count) => count,
+            Err(error) => panic!("{}", error),
+        };
+        const CELLS: [$crate::game_of_life::Point; COUNT] = match $crate::game_of_life::rle::const_live_cells::<COUNT>(RLE) {
+            Ok(cells) => cells,
+            Err(error) => panic!("{}", error),
+        };
+        $crate::game_of_life::StaticPattern {
+            size: $crate::game_of_life::Size::new(SIZE.0, SIZE.1),
+            cells: &CELLS,
+        }
+    }};
+}
+
+const fn is_blank(byte: u8) -> bool {
+    byte == b' ' || byte == b'\t' || byte == b'\r' || byte == b'\n'
+}
+
+/// Index of the first byte of the line following `from`.
+const fn next_line(bytes: &[u8], from: usize) -> usize {
+    let mut i = from;
+    while i < bytes.len() && bytes[i] != b'\n' {
+        i += 1;
+    }
+    if i < bytes.len() { i + 1 } else { i }
+}
+
+/// Index of the first non blank byte at or after `from`.
+const fn skip_blanks(bytes: &[u8], from: usize) -> usize {
+    let mut i = from;
+    while i < bytes.len() && is_blank(bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Index of the `x = ..` header line, skipping `#` comment lines.
+const fn header_start(bytes: &[u8]) -> Result<usize, &'static str> {
+    let mut i = skip_blanks(bytes, 0);
+    while i < bytes.len() && bytes[i] == b'#' {
+        i = skip_blanks(bytes, next_line(bytes, i));
+    }
+    if i < bytes.len() && bytes[i] == b'x' {
+        Ok(i)
+    } else {
+        Err("RLE pattern must start with a `x = .., y = ..` header")
+    }
+}
+
+/// Parse `name = number` at `from`, returning the number and where it ends.
+const fn assignment(bytes: &[u8], from: usize, name: u8) -> Result<(usize, usize), &'static str> {
+    let mut i = skip_blanks(bytes, from);
+    if i >= bytes.len() || bytes[i] != name {
+        return Err("malformed RLE header");
+    }
+    i = skip_blanks(bytes, i + 1);
+    if i >= bytes.len() || bytes[i] != b'=' {
+        return Err("malformed RLE header");
+    }
+    i = skip_blanks(bytes, i + 1);
+    let start = i;
+    let mut value: usize = 0;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        value = match value.checked_mul(10) {
+            Some(value) => match value.checked_add((bytes[i] - b'0') as usize) {
+                Some(value) => value,
+                None => return Err("RLE number too large"),
+            },
+            None => return Err("RLE number too large"),
+        };
+        i += 1;
+    }
+    if i == start {
+        Err("malformed RLE header")
+    } else {
+        Ok((value, i))
+    }
+}
+
+/// Width and height declared by the header.
+pub const fn const_size(rle: &str) -> Result<(usize, usize), &'static str> {
+    let bytes = rle.as_bytes();
+    let start = match header_start(bytes) {
+        Ok(start) => start,
+        Err(error) => return Err(error),
+    };
+    let (width, end) = match assignment(bytes, start, b'x') {
+        Ok(parsed) => parsed,
+        Err(error) => return Err(error),
+    };
+    let comma = skip_blanks(bytes, end);
+    if comma >= bytes.len() || bytes[comma] != b',' {
+        return Err("malformed RLE header");
+    }
+    match assignment(bytes, comma + 1, b'y') {
+        Ok((height, _)) => Ok((width, height)),
+        Err(error) => Err(error),
+    }
+}
+
+/// Walk the cells, storing live ones in `cells` while there is room, and
+/// return how many there are.
+const fn scan(rle: &str, cells: &mut [Point]) -> Result<usize, &'static str> {
+    let (width, height) = match const_size(rle) {
+        Ok(size) => size,
+        Err(error) => return Err(error),
+    };
+    let bytes = rle.as_bytes();
+    let mut i = match header_start(bytes) {
+        Ok(start) => next_line(bytes, start),
+        Err(error) => return Err(error),
+    };
+
+    let (mut x, mut y) = (0, 0);
+    let mut run: usize = 0;
+    let mut count = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        i += 1;
+        if byte.is_ascii_digit() {
+            run = match run.checked_mul(10) {
+                Some(run) => run + (byte - b'0') as usize,
+                None => return Err("RLE number too large"),
+            };
+            continue;
+        }
+        let length = if run == 0 { 1 } else { run };
+        run = 0;
+        match byte {
+            b'b' | b'.' => x += length,
+            b'o' => {
+                if x + length > width || y >= height {
+                    return Err("RLE cell outside of the declared size");
+                }
+                let mut k = 0;
+                while k < length {
+                    if count < cells.len() {
+                        cells[count] = Point::new((x + k) as i64, y as i64);
+                    }
+                    count += 1;
+                    k += 1;
+                }
+                x += length;
+            }
+            b'$' => {
+                x = 0;
+                y += length;
+            }
+            b'!' => return Ok(count),
+            byte if is_blank(byte) => {}
+            _ => return Err("unsupported RLE character, only two state patterns are supported"),
+        }
+    }
+    Err("RLE pattern must end with `!`")
+}
+
+pub const fn const_live_count(rle: &str) -> Result<usize, &'static str> {
+    scan(rle, &mut [])
+}
+
+/// Coordinates of the `N` live cells, `N` must be `const_live_count(rle)`.
+pub const fn const_live_cells<const N: usize>(rle: &str) -> Result<[Point; N], &'static str> {
+    let mut cells = [Point::ORIGIN; N];
+    match scan(rle, &mut cells) {
+        Ok(count) if count == N => Ok(cells),
+        Ok(_) => Err("live cell count does not match"),
+        Err(error) => Err(error),
+    }
+}
+
+impl Pattern {
+
+    /// Parse a pattern in RLE format, with its metadata.
+    ///
+    /// Errors point at the line and column of the offending character.
+    pub fn from_rle(text: &str) -> Result<Self, ParseError> {
+        let mut metadata = Metadata::default();
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line)).filter(|(_, line)| !line.trim().is_empty());
+        let (header_number, header) = loop {
+            let (number, line) = lines.next().ok_or_else(|| ParseError::end_of(text, "RLE pattern must have a `x = .., y = ..` header"))?;
+            match line.trim().strip_prefix('#') {
+                Some(comment) => read_comment(comment, &mut metadata),
+                None => break (number, line),
+            }
+        };
+        let error = |message, offset| ParseError::at(message, header_number, header, offset);
+
+        let (mut width, mut height) = (None, None);
+        let mut offset = 0;
+        for field in header.split(',') {
+            let at = offset;
+            offset += field.len() + 1;
+            let (key, value) = field.split_once('=').ok_or_else(|| error("malformed RLE header", at))?;
+            let value_at = at + key.len() + 1 + (value.len() - value.trim_start().len());
+            let value = value.trim();
+            match key.trim() {
+                "x" => width = Some(value.parse::<usize>().map_err(|_| error("RLE width must be a number", value_at))?),
+                "y" => height = Some(value.parse::<usize>().map_err(|_| error("RLE height must be a number", value_at))?),
+                "rule" => metadata.rule = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(error("RLE header must give both x and y", 0)),
+        };
+        // Cells are bytes, the header must not ask for more than a universe
+        // could hold.
+        if width.checked_mul(height).is_none_or(|cells| cells > memory_limit()) {
+            return Err(error("RLE pattern too large", 0));
+        }
+
+        let mut pattern = Pattern::new(width, height);
+        let (mut x, mut y) = (0usize, 0usize);
+        let mut run: Option<usize> = None;
+        let mut prefix: Option<u8> = None;
+        let mut body = lines.flat_map(|(number, line)| line.bytes().enumerate().map(move |(at, byte)| (number, line, at, byte)));
+        // Where the text ends, if it does before the `!`.
+        let mut end = (header_number, header, header.len());
+        loop {
+            let (number, line, at, byte) = body.next().ok_or_else(|| ParseError::at("RLE pattern must end with `!`", end.0, end.1, end.2))?;
+            end = (number, line, at + 1);
+            let here = |message| ParseError::at(message, number, line, at);
+            if byte.is_ascii_digit() && prefix.is_none() {
+                let digit = (byte - b'0') as usize;
+                run = Some(run.unwrap_or(0).checked_mul(10).and_then(|run| run.checked_add(digit)).ok_or_else(|| here("RLE number too large"))?);
+                continue;
+            }
+            let state = match (prefix.take(), byte) {
+                (None, b'p'..=b'y') => {
+                    prefix = Some(byte);
+                    continue;
+                }
+                (None, b'b') | (None, b'.') => 0,
+                (None, b'o') => 1,
+                (None, b'A'..=b'X') => byte - b'A' + 1,
+                (Some(prefix), b'A'..=b'X') => {
+                    let state = (prefix - b'p' + 1) as usize * 24 + (byte - b'A') as usize + 1;
+                    if state > 255 {
+                        return Err(here("RLE state above 255"));
+                    }
+                    state as u8
+                }
+                (None, b'$') => {
+                    y = y.checked_add(run.take().unwrap_or(1)).ok_or_else(|| here("RLE cell outside of the declared size"))?;
+                    x = 0;
+                    continue;
+                }
+                (None, b'!') => break,
+                (None, byte) if byte.is_ascii_whitespace() => continue,
+                _ => return Err(here("unsupported RLE character")),
+            };
+            let length = run.take().unwrap_or(1);
+            if state != 0 {
+                if x.saturating_add(length) > width || y >= height {
+                    return Err(here("RLE cell outside of the declared size"));
+                }
+                pattern.cells_mut()[y * width + x..y * width + x + length].iter_mut().for_each(|cell| *cell = state);
+            }
+            x = x.saturating_add(length);
+        }
+
+        *pattern.metadata_mut() = metadata;
+        Ok(pattern)
+    }
+
+    /// Write the pattern in RLE format, metadata included.
+    ///
+    /// Two state patterns use `b` and `o`, others Golly's multi-state
+    /// letters.
+    pub fn to_rle(&self) -> String {
+        let metadata = self.metadata();
+        let mut text = String::new();
+        if let Some(name) = &metadata.name {
+            text += &format!("#N {}\n", name);
+        }
+        if let Some(author) = &metadata.author {
+            text += &format!("#O {}\n", author);
+        }
+        for comment in &metadata.comments {
+            text += &format!("#C {}\n", comment);
+        }
+        text += &format!("x = {}, y = {}", self.width(), self.height());
+        if let Some(rule) = &metadata.rule {
+            text += &format!(", rule = {}", rule);
+        }
+        text.push('\n');
+
+        let multi_state = self.cells().iter().any(|&cell| cell > 1);
+        let mut writer = RunWriter::default();
+        for row in self.cells().chunks(self.width().max(1)) {
+            // Dead cells at the end of a row are implied.
+            let row = &row[..row.iter().rposition(|&cell| cell != 0).map_or(0, |last| last + 1)];
+            let mut x = 0;
+            while x < row.len() {
+                let length = row[x..].iter().take_while(|&&cell| cell == row[x]).count();
+                writer.run(length, &state_symbol(row[x], multi_state));
+                x += length;
+            }
+            writer.run(1, "$");
+        }
+        text + &writer.finish()
+    }
+
+}
+
+/// Read the metadata of a `#` line, `comment` being what follows the `#`.
+fn read_comment(comment: &str, metadata: &mut Metadata) {
+    let (kind, value) = comment.split_at(comment.chars().next().map_or(0, char::len_utf8));
+    let value = value.trim().to_string();
+    match kind {
+        "N" => metadata.name = Some(value),
+        "O" => metadata.author = Some(value),
+        "C" | "c" => metadata.comments.push(value),
+        _ => {}
+    }
+}
+
+fn state_symbol(state: u8, multi_state: bool) -> String {
+    match (state, multi_state) {
+        (0, false) => "b".to_string(),
+        (_, false) => "o".to_string(),
+        (0, true) => ".".to_string(),
+        (state, true) => {
+            let (prefix, letter) = ((state - 1) / 24, (state - 1) % 24);
+            let letter = char::from(b'A' + letter);
+            match prefix {
+                0 => letter.to_string(),
+                prefix => format!("{}{}", char::from(b'p' + prefix - 1), letter),
+            }
+        }
+    }
+}
+
+/// Merges runs of the same symbol, and `$` runs, and wraps lines.
+#[derive(Default)]
+struct RunWriter {
+    text: String,
+    line: usize,
+    pending: Option<(usize, String)>,
+}
+
+impl RunWriter {
+
+    fn run(&mut self, length: usize, symbol: &str) {
+        match &mut self.pending {
+            Some((count, pending)) if pending == symbol => *count += length,
+            _ => {
+                self.flush();
+                self.pending = Some((length, symbol.to_string()));
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some((count, symbol)) = self.pending.take() {
+            let token = if count == 1 { symbol } else { format!("{}{}", count, symbol) };
+            self.push(&token);
+        }
+    }
+
+    fn push(&mut self, token: &str) {
+        if self.line + token.len() > LINE_LENGTH {
+            self.text.push('\n');
+            self.line = 0;
+        }
+        self.text += token;
+        self.line += token.len();
+    }
+
+    fn finish(mut self) -> String {
+        // The last rows end the pattern, their `$` are not needed.
+        if matches!(&self.pending, Some((_, symbol)) if symbol == "$") {
+            self.pending = None;
+        }
+        self.flush();
+        self.push("!");
+        self.text.push('\n');
+        self.text
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{const_live_count, const_size};
+    use crate::game_of_life::{Pattern, Point, Size, StaticPattern};
+
+    static LWSS: StaticPattern = crate::pattern!("
+#N Lightweight spaceship
+#C A comment.
+x = 5, y = 4, rule = B3/S23
+bo2bo$o4b$o3bo$
+4o!
+");
+
+    #[test]
+    fn test_pattern_macro() {
+        assert_eq!(LWSS.size, Size::new(5, 4));
+        let cells = [(1, 0), (4, 0), (0, 1), (0, 2), (4, 2), (0, 3), (1, 3), (2, 3), (3, 3)];
+        assert!(LWSS.cells.iter().copied().eq(cells.iter().map(|&(x, y)| Point::new(x, y))));
+
+        let pattern = LWSS.to_pattern();
+        assert_eq!(pattern.population(), 9);
+        assert_eq!(pattern.get(4, 0).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_const_parser_errors() {
+        assert!(const_size("bo$2bo!").is_err());
+        assert!(const_size("x = 3 y = 3").is_err());
+        assert!(const_live_count("x = 3, y = 3\nbob$2bo$3o").is_err());
+        assert!(const_live_count("x = 3, y = 2\nbob$2bo$3o!").is_err());
+        assert!(const_live_count("x = 3, y = 3\nbAb!").is_err());
+        assert_eq!(const_live_count("x = 36, y = 1\n12b24o!"), Ok(24));
+    }
+
+    #[test]
+    fn test_rle_error_positions() {
+        let at = |text: &str| Pattern::from_rle(text).map(|_| ()).map_err(|error| (error.message, error.line, error.column));
+        assert_eq!(at("#N Glider\n\nx = 3, y = 3\nbo$2bo$3zo!"), Err(("unsupported RLE character", 4, 9)));
+        assert_eq!(at("x = 3, y = 2.5\nbo!"), Err(("RLE height must be a number", 1, 12)));
+        assert_eq!(at("x = 3\nbo!"), Err(("RLE header must give both x and y", 1, 1)));
+        assert_eq!(at("x = 3, y = 1\n4o!"), Err(("RLE cell outside of the declared size", 2, 2)));
+        assert_eq!(at("x = 3, y = 1\nb\n  2o"), Err(("RLE pattern must end with `!`", 3, 5)));
+        assert_eq!(at("#C é\n"), Err(("RLE pattern must have a `x = .., y = ..` header", 1, 5)));
+        assert_eq!(Pattern::from_rle("x = 1, y = 1\n2A!").unwrap_err().to_string(), "RLE cell outside of the declared size at line 2, column 2");
+    }
+
+    #[test]
+    fn test_rle_round_trip() {
+        let text = "#N Glider\n#O Richard K. Guy\n#C The smallest spaceship.\nx = 3, y = 4, rule = B3/S23\nbo$2bo$3o!\n";
+        let glider = Pattern::from_rle(text).unwrap();
+        assert_eq!(glider.cells(), &[0, 1, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0]);
+        let metadata = glider.metadata();
+        assert_eq!(metadata.name.as_deref(), Some("Glider"));
+        assert_eq!(metadata.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(metadata.comments, vec!["The smallest spaceship.".to_string()]);
+        assert_eq!(metadata.rule.as_deref(), Some("B3/S23"));
+        assert_eq!(glider.to_rle(), text);
+        let rule = glider.rule_hint().unwrap().unwrap();
+        assert_eq!(rule.next_state(0, &[1, 1, 1, 0, 0, 0, 0, 0]), 1);
+
+        let mut wide = Pattern::new(100, 3);
+        wide.set(99, 0, 1).unwrap();
+        wide.set(0, 2, 30).unwrap();
+        wide.set(1, 2, 2).unwrap();
+        let rle = wide.to_rle();
+        assert_eq!(rle, "x = 100, y = 3\n99.A2$pFB!\n");
+        assert_eq!(Pattern::from_rle(&rle).unwrap(), wide);
+
+        let long = Pattern::from_cells(80, 1, (0..80).map(|x| x as u8 % 2).collect()).unwrap();
+        let rle = long.to_rle();
+        assert!(rle.lines().all(|line| line.len() <= 70));
+        assert_eq!(Pattern::from_rle(&rle).unwrap(), long);
+    }
+
+    #[test]
+    fn test_rle_errors() {
+        assert!(Pattern::from_rle("bo$2bo$3o!").is_err());
+        assert!(Pattern::from_rle("x = 3, y = 3\nbo$2bo$3o").is_err());
+        assert!(Pattern::from_rle("x = 2, y = 3\nbo$2bo$3o!").is_err());
+        assert!(Pattern::from_rle("x = 3, y = 1\nzA!").is_err());
+    }
+
+}
@@ -0,0 +1,154 @@
+use super::{Neighbors, Rule, RuleConfig};
+use crate::game_of_life::{Color, Palette, ParseError, Size};
+
+/// Life-like rule where dying cells go through decay states before being
+/// dead, like Brian's Brain (`B2/S/C3`) or Star Wars (`B2/S345/C4`).
+///
+/// State 0 is dead, 1 alive and every state above is one more step of
+/// decay. Only alive cells count as neighbors, alive cells that do not
+/// survive start to decay, and decaying cells can not be born.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Generations {
+    birth: [bool; 9],
+    survival: [bool; 9],
+    states: usize,
+}
+
+impl Generations {
+
+    /// Parse `B2/S/C3` (or `B2/S/3`), or the older `S/B/C` form `/2/3`.
+    pub fn parse(rulestring: &str) -> Result<Self, ParseError> {
+        let error = |message, offset| ParseError::at(message, 1, rulestring, offset);
+        let indent = rulestring.len() - rulestring.trim_start().len();
+        // Every part with the offset it starts at, for errors to point at.
+        let mut parts = Vec::new();
+        let mut offset = indent;
+        for part in rulestring.trim().split('/') {
+            parts.push((offset, part.to_ascii_uppercase()));
+            offset += part.len() + 1;
+        }
+        if parts.len() != 3 {
+            return Err(error("generations rule must have three parts", indent));
+        }
+        let ((birth_at, birth), (survival_at, survival)) = match parts[0].1.strip_prefix('B') {
+            Some(birth) => {
+                let survival = parts[1].1.strip_prefix('S').ok_or_else(|| error("expected S after B", parts[1].0))?;
+                ((parts[0].0 + 1, birth), (parts[1].0 + 1, survival))
+            }
+            None => ((parts[1].0, parts[1].1.as_str()), (parts[0].0, parts[0].1.as_str())),
+        };
+        let (count_at, count) = &parts[2];
+        let states = count
+            .strip_prefix(|c| c == 'C' || c == 'G')
+            .unwrap_or(count)
+            .parse::<usize>()
+            .ok()
+            .filter(|states| (2..=256).contains(states))
+            .ok_or_else(|| error("state count must be between 2 and 256", *count_at))?;
+
+        Ok(Generations {
+            birth: parse_counts(birth).map_err(|at| error("neighbor counts must be digits from 0 to 8", birth_at + at))?,
+            survival: parse_counts(survival).map_err(|at| error("neighbor counts must be digits from 0 to 8", survival_at + at))?,
+            states,
+        })
+    }
+
+    /// Conway's Life, `B3/S23`.
+    pub const fn life() -> Self {
+        let mut birth = [false; 9];
+        let mut survival = [false; 9];
+        birth[3] = true;
+        survival[2] = true;
+        survival[3] = true;
+        Generations { birth, survival, states: 2 }
+    }
+
+    /// Canonical `B/S/C` rulestring.
+    pub fn rulestring(&self) -> String {
+        let digits = |counts: &[bool; 9]| -> String {
+            (0..9).filter(|&n| counts[n]).map(|n| char::from(b'0' + n as u8)).collect()
+        };
+        format!("B{}/S{}/C{}", digits(&self.birth), digits(&self.survival), self.states)
+    }
+
+}
+
+/// The counts of ASCII digits, or the offset of the first other character.
+fn parse_counts(digits: &str) -> Result<[bool; 9], usize> {
+    let mut counts = [false; 9];
+    for (at, digit) in digits.char_indices() {
+        match digit.to_digit(10) {
+            Some(n) if n <= 8 => counts[n as usize] = true,
+            _ => return Err(at),
+        }
+    }
+    Ok(counts)
+}
+
+impl Rule for Generations {
+
+    fn states(&self) -> usize {
+        self.states
+    }
+
+    fn next_state(&self, cell: u8, neighbors: &Neighbors) -> u8 {
+        let alive = neighbors.iter().filter(|&&neighbor| neighbor == 1).count();
+        match cell {
+            0 if self.birth[alive] => 1,
+            0 => 0,
+            1 if self.survival[alive] => 1,
+            decaying if (decaying as usize) + 1 < self.states => decaying + 1,
+            _ => 0,
+        }
+    }
+
+    /// Alive cells are white and fade from yellow to dark red as they decay.
+    fn palette(&self) -> Palette {
+        Palette::fade(self.states, Color::new(255, 255, 255), Color::new(255, 220, 0), Color::new(80, 0, 0))
+    }
+
+    /// Rules with births on one or two neighbors, like Seeds or Brian's
+    /// Brain, explode from anything denser than a sprinkle of cells, and
+    /// move too fast to follow at full speed.
+    fn suggested_config(&self) -> RuleConfig {
+        let explosive = self.birth[1] || self.birth[2];
+        RuleConfig {
+            size: Size::new(128, 128),
+            density: if explosive { 0.05 } else { 0.3 },
+            palette: self.palette(),
+            generations_per_second: if explosive { 5 } else { 10 },
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Generations, Rule};
+
+    #[test]
+    fn test_parse() {
+        let brain = Generations::parse("B2/S/C3").unwrap();
+        assert_eq!(brain.states(), 3);
+        assert_eq!(Generations::parse("/2/3").unwrap(), brain);
+        assert_eq!(Generations::parse("b2/s/3").unwrap().rulestring(), "B2/S/C3");
+        assert!(Generations::parse("B9/S/C3").is_err());
+        assert!(Generations::parse("B2/S/C1").is_err());
+        assert!(Generations::parse("B2/S").is_err());
+        assert_eq!(Generations::parse(" B3/S2x3/C2").unwrap_err().column, 7);
+        assert_eq!(Generations::parse("B3/23/C2").unwrap_err().column, 4);
+        assert_eq!(Generations::parse("B3/S23/C٣").unwrap_err().column, 8);
+    }
+
+    #[test]
+    fn test_brians_brain() {
+        let brain = Generations::parse("B2/S/C3").unwrap();
+        assert_eq!(brain.next_state(0, &[1, 0, 1, 0, 0, 0, 0, 0]), 1);
+        // Decaying neighbors do not count.
+        assert_eq!(brain.next_state(0, &[1, 0, 2, 0, 0, 0, 0, 0]), 0);
+        assert_eq!(brain.next_state(1, &[1, 0, 1, 0, 0, 0, 0, 0]), 2);
+        assert_eq!(brain.next_state(2, &[1, 0, 1, 0, 0, 0, 0, 0]), 0);
+        assert!(brain.suggested_config().density < Generations::parse("B3/S23/C2").unwrap().suggested_config().density);
+    }
+
+}
@@ -0,0 +1,70 @@
+//! Helpers for Golly's `.rule` files.
+//!
+//! A `.rule` file is a list of sections, each one starting with a line like
+//! `@TABLE` or `@RULE name`. Anything before the first section is ignored.
+
+use super::{Rule, TableRule, TreeRule};
+use crate::game_of_life::ParseError;
+
+/// One `@NAME argument` section and the lines following it.
+pub struct Section<'a> {
+    pub name: &'a str,
+    pub argument: &'a str,
+    pub lines: Vec<&'a str>,
+    /// Line of the `@NAME` header in the file, from 1.
+    pub line: usize,
+}
+
+/// Split a `.rule` file into its sections.
+pub fn sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections: Vec<Section> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if let Some(header) = line.trim().strip_prefix('@') {
+            let (name, argument) = match header.find(char::is_whitespace) {
+                Some(split) => (&header[..split], header[split..].trim()),
+                None => (header, ""),
+            };
+            sections.push(Section { name, argument, lines: Vec::new(), line: number + 1 });
+        } else if let Some(section) = sections.last_mut() {
+            section.lines.push(line);
+        }
+    }
+    sections
+}
+
+/// Find a section by name, names are case sensitive like in Golly.
+pub fn section<'a>(text: &'a str, name: &str) -> Option<Section<'a>> {
+    sections(text).into_iter().find(|section| section.name == name)
+}
+
+/// Drop the `#` comment and surrounding blanks of a line.
+pub fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(start) => line[..start].trim(),
+        None => line.trim(),
+    }
+}
+
+/// An error on `lines[index]`, at its first non blank character, lines
+/// counting from 1. Past the last line, the error is at the end of it.
+pub(crate) fn line_error(lines: &[&str], index: usize, message: &'static str) -> ParseError {
+    match lines.get(index) {
+        Some(line) => ParseError::at(message, index + 1, line, line.len() - line.trim_start().len()),
+        None => {
+            let last = lines.last().copied().unwrap_or("");
+            ParseError::at(message, lines.len().max(1), last, last.len())
+        }
+    }
+}
+
+/// Load the rule of a `.rule` file from its `@TABLE` section, or from its
+/// `@TREE` section when it has no table.
+pub fn load(text: &str) -> Result<Box<dyn Rule>, ParseError> {
+    if section(text, "TABLE").is_some() {
+        Ok(Box::new(TableRule::from_rule_file(text)?))
+    } else if section(text, "TREE").is_some() {
+        Ok(Box::new(TreeRule::from_rule_file(text)?))
+    } else {
+        Err(ParseError::end_of(text, "rule file has neither a @TABLE nor a @TREE section"))
+    }
+}
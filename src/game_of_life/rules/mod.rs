@@ -0,0 +1,85 @@
+use super::{InitPolicy, Palette, ParseError, Point, Size};
+
+pub mod generations;
+pub mod golly;
+pub mod table;
+pub mod testkit;
+pub mod tree;
+
+pub use generations::Generations;
+pub use table::{Neighborhood, TableRule};
+pub use tree::TreeRule;
+
+/// Parse a rulestring: life-like `B3/S23` (or `23/3`), or Generations
+/// `B2/S/C3`.
+///
+/// Only ASCII letters and digits are read, whatever the locale, and the
+/// `rulestring` of a rule is its canonical form.
+pub fn parse(rulestring: &str) -> Result<Box<dyn Rule>, ParseError> {
+    match rulestring.split('/').count() {
+        2 => Ok(Box::new(Generations::parse(&format!("{}/2", rulestring))?)),
+        3 => Ok(Box::new(Generations::parse(rulestring)?)),
+        _ => Err(ParseError::at("unknown rulestring", 1, rulestring, 0)),
+    }
+}
+
+/// States of the eight cells around a cell, in Golly's order:
+/// N, NE, E, SE, S, SW, W, NW.
+pub type Neighbors = [u8; 8];
+
+/// Position of every entry of `Neighbors` relative to the center cell, y
+/// going down.
+pub const NEIGHBOR_OFFSETS: [Point; 8] = [
+    Point::new(0, -1),
+    Point::new(1, -1),
+    Point::new(1, 0),
+    Point::new(1, 1),
+    Point::new(0, 1),
+    Point::new(-1, 1),
+    Point::new(-1, 0),
+    Point::new(-1, -1),
+];
+
+/// Settings a rule looks good with, for frontends to apply when the user
+/// switches to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleConfig {
+    pub size: Size,
+    /// Probability of a cell to start alive.
+    pub density: f64,
+    pub palette: Palette,
+    pub generations_per_second: u32,
+}
+
+impl RuleConfig {
+
+    /// Random initialization at the suggested density.
+    pub fn init_policy(&self) -> InitPolicy {
+        InitPolicy::Random{alive_probability: self.density}
+    }
+
+}
+
+/// A rule gives the next state of a cell from its current state and the
+/// states of its neighbors.
+pub trait Rule {
+    /// Number of states a cell can be in, between 2 and 256.
+    fn states(&self) -> usize;
+
+    fn next_state(&self, cell: u8, neighbors: &Neighbors) -> u8;
+
+    /// Colors the states of this rule look best with.
+    fn palette(&self) -> Palette {
+        Palette::default_for(self.states())
+    }
+
+    /// Defaults to start a random universe of this rule with.
+    fn suggested_config(&self) -> RuleConfig {
+        RuleConfig {
+            size: Size::new(128, 128),
+            density: 0.3,
+            palette: self.palette(),
+            generations_per_second: 10,
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use std::collections::{HashMap, HashSet};
+
+use super::golly;
+use super::{Neighbors, Rule};
+use crate::game_of_life::ParseError;
+
+/// Cells a table transition looks at, besides the center cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    Moore,
+    VonNeumann,
+}
+
+impl Neighborhood {
+
+    /// Indexes in `Neighbors` of the cells listed after the center, in the
+    /// order transitions list them.
+    pub fn positions(&self) -> &'static [usize] {
+        match self {
+            Neighborhood::Moore => &[0, 1, 2, 3, 4, 5, 6, 7],
+            Neighborhood::VonNeumann => &[0, 2, 4, 6],
+        }
+    }
+
+}
+
+/// Equivalent arrangements of the neighbors of a transition.
+enum Symmetries {
+    /// Every listed mapping of the neighbor positions.
+    Mappings(Vec<Vec<usize>>),
+    /// Any order of the neighbors.
+    Permute,
+}
+
+impl Symmetries {
+
+    fn parse(name: &str, neighborhood: Neighborhood) -> Result<Self, &'static str> {
+        let count = neighborhood.positions().len();
+        // Rotations are expressed in steps of one neighbor.
+        let (rotations, reflect): (&[usize], bool) = match (name, neighborhood) {
+            ("none", _) => (&[0], false),
+            ("permute", _) => return Ok(Symmetries::Permute),
+            ("reflect_horizontal", _) => (&[0], true),
+            ("rotate2", Neighborhood::Moore) => (&[0, 4], false),
+            ("rotate4", Neighborhood::Moore) => (&[0, 2, 4, 6], false),
+            ("rotate8", Neighborhood::Moore) => (&[0, 1, 2, 3, 4, 5, 6, 7], false),
+            ("rotate4reflect", Neighborhood::Moore) => (&[0, 2, 4, 6], true),
+            ("rotate8reflect", Neighborhood::Moore) => (&[0, 1, 2, 3, 4, 5, 6, 7], true),
+            ("rotate2", Neighborhood::VonNeumann) => (&[0, 2], false),
+            ("rotate4", Neighborhood::VonNeumann) => (&[0, 1, 2, 3], false),
+            ("rotate4reflect", Neighborhood::VonNeumann) => (&[0, 1, 2, 3], true),
+            _ => return Err("unsupported symmetries for this neighborhood"),
+        };
+        let mut mappings = Vec::new();
+        for &rotation in rotations {
+            let rotated: Vec<usize> = (0..count).map(|i| (i + rotation) % count).collect();
+            if reflect {
+                // Mirror around the vertical axis, N and S stay in place.
+                mappings.push(rotated.iter().map(|&i| (count - i) % count).collect());
+            }
+            mappings.push(rotated);
+        }
+        Ok(Symmetries::Mappings(mappings))
+    }
+
+    /// Every arrangement of `neighbors` equivalent to it, duplicates included.
+    fn apply(&self, neighbors: &[Vec<u8>]) -> Vec<Vec<Vec<u8>>> {
+        match self {
+            Symmetries::Mappings(mappings) => mappings
+                .iter()
+                .map(|mapping| mapping.iter().map(|&i| neighbors[i].clone()).collect())
+                .collect(),
+            Symmetries::Permute => {
+                // Walk the distinct permutations in lexicographic order.
+                let mut current = neighbors.to_vec();
+                current.sort();
+                let mut arrangements = vec![current.clone()];
+                while let Some(pivot) = (1..current.len()).rev().find(|&i| current[i - 1] < current[i]) {
+                    let pivot = pivot - 1;
+                    let successor = (pivot + 1..current.len()).rev().find(|&i| current[pivot] < current[i]).unwrap();
+                    current.swap(pivot, successor);
+                    current[pivot + 1..].reverse();
+                    arrangements.push(current.clone());
+                }
+                arrangements
+            }
+        }
+    }
+
+}
+
+/// Part of a transition: a set of states, or a variable.
+enum Term {
+    Values(Vec<u8>),
+    Variable(String),
+}
+
+/// A rule loaded from the `@TABLE` section of a Golly `.rule` file.
+///
+/// Transitions are tried in order, the first one matching a cell and its
+/// neighbors gives its next state. Cells matching no transition keep their
+/// state. Variables appearing more than once in a transition are bound, they
+/// take the same value everywhere in that transition.
+///
+/// Once variables and symmetries are expanded, transitions are compiled into
+/// one bit set per position and state, marking the transitions accepting that
+/// state at that position. Finding the first matching transition is then a
+/// few AND and a trailing zeros count for each 64 transitions.
+pub struct TableRule {
+    name: String,
+    states: usize,
+    neighborhood: Neighborhood,
+    words: usize,
+    masks: Vec<u64>,
+    outputs: Vec<u8>,
+}
+
+impl TableRule {
+
+    /// Load the `@TABLE` section of a `.rule` file, named after its `@RULE` line.
+    pub fn from_rule_file(text: &str) -> Result<Self, ParseError> {
+        let table = golly::section(text, "TABLE").ok_or_else(|| ParseError::end_of(text, "no @TABLE section in rule file"))?;
+        let name = golly::section(text, "RULE").map_or("", |section| section.argument);
+        TableRule::parse(name, &table.lines).map_err(|error| error.after_line(table.line))
+    }
+
+    /// Compile the lines of a `@TABLE` section, errors counting lines from
+    /// the first one given.
+    pub fn parse(name: &str, lines: &[&str]) -> Result<Self, ParseError> {
+        let mut states = None;
+        let mut neighborhood = None;
+        let mut symmetries = None;
+        let mut variables: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut transitions: Vec<(Vec<Vec<u8>>, u8)> = Vec::new();
+
+        let mut read_line = |line: &str| -> Result<(), &'static str> {
+            if line.is_empty() {
+                return Ok(());
+            }
+
+            if let Some(definition) = line.strip_prefix("var ") {
+                let states = states.ok_or("n_states must be declared before variables")?;
+                let (variable, values) = definition.split_once('=').ok_or("invalid variable definition")?;
+                let values = match parse_term(values.trim(), states, &variables)? {
+                    Term::Values(values) => values,
+                    Term::Variable(other) => variables[&other].clone(),
+                };
+                variables.insert(variable.trim().to_string(), values);
+            } else if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" => states = Some(
+                        value.parse::<usize>()
+                            .ok()
+                            .filter(|states| (2..=256).contains(states))
+                            .ok_or("n_states must be between 2 and 256")?
+                    ),
+                    "neighborhood" => neighborhood = Some(match value {
+                        "Moore" => Neighborhood::Moore,
+                        "vonNeumann" => Neighborhood::VonNeumann,
+                        _ => return Err("unsupported neighborhood"),
+                    }),
+                    "symmetries" => symmetries = Some(Symmetries::parse(
+                        value,
+                        neighborhood.ok_or("neighborhood must be declared before symmetries")?,
+                    )?),
+                    _ => return Err("unknown table header"),
+                }
+            } else {
+                let states = states.ok_or("n_states must be declared before transitions")?;
+                let neighborhood = neighborhood.ok_or("neighborhood must be declared before transitions")?;
+                let symmetries = symmetries.as_ref().ok_or("symmetries must be declared before transitions")?;
+                expand_transition(line, states, neighborhood, symmetries, &variables, &mut transitions)?;
+            }
+            Ok(())
+        };
+        for (index, line) in lines.iter().enumerate() {
+            read_line(golly::strip_comment(line)).map_err(|message| golly::line_error(lines, index, message))?;
+        }
+
+        let missing = |message| golly::line_error(lines, lines.len(), message);
+        let states = states.ok_or_else(|| missing("missing n_states"))?;
+        let neighborhood = neighborhood.ok_or_else(|| missing("missing neighborhood"))?;
+        let positions = neighborhood.positions().len() + 1;
+        let words = transitions.len().div_ceil(64);
+        let mut masks = vec![0u64; positions * states * words];
+        let mut outputs = Vec::with_capacity(transitions.len());
+        for (index, (inputs, output)) in transitions.into_iter().enumerate() {
+            for (position, values) in inputs.iter().enumerate() {
+                for &value in values {
+                    masks[(position * states + value as usize) * words + index / 64] |= 1 << (index % 64);
+                }
+            }
+            outputs.push(output);
+        }
+
+        Ok(TableRule {
+            name: name.to_string(),
+            states,
+            neighborhood,
+            words,
+            masks,
+            outputs,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Number of transitions once variables and symmetries are expanded.
+    pub fn transitions(&self) -> usize {
+        self.outputs.len()
+    }
+
+    fn mask(&self, position: usize, state: u8, word: usize) -> u64 {
+        if state as usize >= self.states {
+            0
+        } else {
+            self.masks[(position * self.states + state as usize) * self.words + word]
+        }
+    }
+
+}
+
+impl Rule for TableRule {
+
+    fn states(&self) -> usize {
+        self.states
+    }
+
+    fn next_state(&self, cell: u8, neighbors: &Neighbors) -> u8 {
+        let positions = self.neighborhood.positions();
+        for word in 0..self.words {
+            let mut matching = self.mask(0, cell, word);
+            for (position, &index) in positions.iter().enumerate() {
+                if matching == 0 {
+                    break;
+                }
+                matching &= self.mask(position + 1, neighbors[index], word);
+            }
+            if matching != 0 {
+                return self.outputs[word * 64 + matching.trailing_zeros() as usize];
+            }
+        }
+        cell
+    }
+
+}
+
+/// A state, an inline `{0,1,a}` list or a variable name.
+fn parse_term(text: &str, states: usize, variables: &HashMap<String, Vec<u8>>) -> Result<Term, &'static str> {
+    let parse_state = |text: &str| {
+        text.parse::<usize>()
+            .ok()
+            .filter(|&state| state < states)
+            .map(|state| state as u8)
+            .ok_or("state out of range")
+    };
+
+    if let Some(list) = text.strip_prefix('{').and_then(|text| text.strip_suffix('}')) {
+        let mut values = Vec::new();
+        for element in list.split(',').map(str::trim) {
+            match parse_term(element, states, variables)? {
+                Term::Values(more) => values.extend(more),
+                Term::Variable(variable) => values.extend(&variables[&variable]),
+            }
+        }
+        values.sort_unstable();
+        values.dedup();
+        Ok(Term::Values(values))
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        Ok(Term::Values(vec![parse_state(text)?]))
+    } else if variables.contains_key(text) {
+        Ok(Term::Variable(text.to_string()))
+    } else {
+        Err("unknown variable")
+    }
+}
+
+/// Split a transition on commas and blanks, or per character for the compact
+/// form without separators.
+fn tokenize(line: &str) -> Vec<&str> {
+    let is_separator = |c: char| c == ',' || c.is_whitespace();
+    if !line.contains(is_separator) {
+        return line.char_indices().map(|(i, c)| &line[i..i + c.len_utf8()]).collect();
+    }
+
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            c if depth == 0 && is_separator(c) => {
+                if start < i {
+                    tokens.push(&line[start..i]);
+                }
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Turn one transition line into concrete transitions: bound variables are
+/// replaced by each of their values and symmetric arrangements are added.
+fn expand_transition(
+    line: &str,
+    states: usize,
+    neighborhood: Neighborhood,
+    symmetries: &Symmetries,
+    variables: &HashMap<String, Vec<u8>>,
+    transitions: &mut Vec<(Vec<Vec<u8>>, u8)>,
+) -> Result<(), &'static str> {
+    let tokens = tokenize(line);
+    if tokens.len() != neighborhood.positions().len() + 2 {
+        return Err("wrong number of states in transition");
+    }
+    let terms = tokens
+        .iter()
+        .map(|token| parse_term(token, states, variables))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bound: Vec<&str> = Vec::new();
+    for term in &terms {
+        if let Term::Variable(variable) = term {
+            let occurrences = terms.iter().filter(|other| matches!(other, Term::Variable(o) if o == variable)).count();
+            if occurrences > 1 && !bound.contains(&variable.as_str()) {
+                bound.push(variable);
+            }
+        }
+    }
+
+    // Odometer over the values of the bound variables.
+    let mut choice = vec![0; bound.len()];
+    loop {
+        let value_of = |variable: &str| {
+            bound.iter().position(|&b| b == variable).map(|i| variables[variable][choice[i]])
+        };
+        let sets: Vec<Vec<u8>> = terms
+            .iter()
+            .map(|term| match term {
+                Term::Values(values) => values.clone(),
+                Term::Variable(variable) => match value_of(variable) {
+                    Some(value) => vec![value],
+                    None => variables[variable].clone(),
+                },
+            })
+            .collect();
+        let output = match sets.last() {
+            Some(values) if values.len() == 1 => values[0],
+            _ => return Err("transition output must be a state or a bound variable"),
+        };
+
+        let center = &sets[0];
+        let neighbors = &sets[1..sets.len() - 1];
+        let mut seen = HashSet::new();
+        for arrangement in symmetries.apply(neighbors) {
+            if seen.insert(arrangement.clone()) {
+                let mut inputs = Vec::with_capacity(arrangement.len() + 1);
+                inputs.push(center.clone());
+                inputs.extend(arrangement);
+                transitions.push((inputs, output));
+            }
+        }
+
+        let mut digit = 0;
+        while digit < choice.len() {
+            choice[digit] += 1;
+            if choice[digit] < variables[bound[digit]].len() {
+                break;
+            }
+            choice[digit] = 0;
+            digit += 1;
+        }
+        if digit == choice.len() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rule, TableRule};
+
+    const LIFE: &str = "
+@RULE LifeTable
+Conway's Game of Life as a table.
+
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:permute
+var a={0,1}
+var b={0,1}
+var c={0,1}
+var d={0,1}
+var e={0,1}
+var f={0,1}
+var g={0,1}
+var h={0,1}
+0,1,1,1,0,0,0,0,0,1   # birth on 3
+1,1,1,0,0,0,0,0,0,1   # survival on 2
+1,1,1,1,0,0,0,0,0,1   # survival on 3
+1,a,b,c,d,e,f,g,h,0
+";
+
+    const WIREWORLD: &str = "
+@RULE WireWorld
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+var i={0,2,3}
+var j={0,2,3}
+var k={0,2,3}
+var l={0,2,3}
+var m={0,2,3}
+var n={0,2,3}
+var o={0,2,3}
+1,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,3
+3,1,i,j,k,l,m,n,o,1
+3,1,1,i,j,k,l,m,n,1
+";
+
+    #[test]
+    fn test_life_table() {
+        let rule = TableRule::from_rule_file(LIFE).unwrap();
+        assert_eq!(rule.name(), "LifeTable");
+        assert_eq!(rule.states(), 2);
+
+        assert_eq!(rule.next_state(0, &[0, 0, 1, 0, 1, 0, 1, 0]), 1);
+        assert_eq!(rule.next_state(0, &[0, 0, 1, 0, 1, 0, 0, 0]), 0);
+        assert_eq!(rule.next_state(1, &[1, 0, 0, 0, 0, 0, 0, 1]), 1);
+        assert_eq!(rule.next_state(1, &[1, 1, 1, 1, 0, 0, 0, 0]), 0);
+        assert_eq!(rule.next_state(1, &[0; 8]), 0);
+    }
+
+    #[test]
+    fn test_wireworld_table() {
+        let rule = TableRule::from_rule_file(WIREWORLD).unwrap();
+        assert_eq!(rule.states(), 4);
+
+        // Head becomes tail, tail becomes conductor.
+        assert_eq!(rule.next_state(1, &[3; 8]), 2);
+        assert_eq!(rule.next_state(2, &[3; 8]), 3);
+        // Conductor fires next to one or two heads, not three.
+        assert_eq!(rule.next_state(3, &[0, 0, 0, 1, 0, 0, 0, 0]), 1);
+        assert_eq!(rule.next_state(3, &[1, 0, 0, 2, 0, 0, 0, 1]), 1);
+        assert_eq!(rule.next_state(3, &[1, 1, 0, 0, 0, 0, 0, 1]), 3);
+        // Empty cells match nothing and stay empty.
+        assert_eq!(rule.next_state(0, &[1; 8]), 0);
+    }
+
+    #[test]
+    fn test_bound_variables_and_rotations() {
+        // A cell copies its north neighbor when north and south agree, in
+        // every rotation: so east and west work too.
+        let rule = TableRule::parse("", &[
+            "n_states:3",
+            "neighborhood:vonNeumann",
+            "symmetries:rotate4",
+            "var a={1,2}",
+            "var b={0,1,2}",
+            "var c={0,1,2}",
+            "0,a,b,a,c,a",
+        ]).unwrap();
+        assert_eq!(rule.next_state(0, &[2, 0, 0, 0, 2, 0, 1, 0]), 2);
+        assert_eq!(rule.next_state(0, &[0, 0, 1, 0, 2, 0, 1, 0]), 1);
+        assert_eq!(rule.next_state(0, &[1, 0, 0, 0, 2, 0, 0, 0]), 0);
+    }
+
+    #[test]
+    fn test_invalid_tables() {
+        assert!(TableRule::from_rule_file("@RULE nothing").is_err());
+        assert!(TableRule::parse("", &["n_states:2", "neighborhood:Moore", "symmetries:none", "0,1,1"]).is_err());
+        assert!(TableRule::parse("", &["n_states:2", "neighborhood:vonNeumann", "symmetries:rotate8"]).is_err());
+        assert!(TableRule::parse("", &["n_states:2", "neighborhood:vonNeumann", "symmetries:none", "0,1,1,1,z,1"]).is_err());
+        assert!(TableRule::parse("", &["n_states:2", "neighborhood:vonNeumann", "symmetries:none", "0,1,1,1,2,1"]).is_err());
+
+        let text = "@RULE bad\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:none\n  0,1,1,1,z,1";
+        let error = TableRule::from_rule_file(text).err().unwrap();
+        assert_eq!((error.line, error.column), (6, 3));
+    }
+
+}
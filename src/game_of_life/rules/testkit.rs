@@ -0,0 +1,129 @@
+//! Checks for authors of custom rules, to run from their own tests.
+//!
+//! The invariant checks go through every neighborhood of every state,
+//! `states^9` transitions: they are meant for rules of a few states.
+
+use std::fmt;
+
+use super::{Neighbors, Rule};
+
+/// A transition breaking an invariant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub center: u8,
+    pub neighbors: Neighbors,
+    /// What the rule gave.
+    pub next: u8,
+    /// What the invariant wanted.
+    pub expected: u8,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "state {} with neighbors {:?} goes to {}, expected {}", self.center, self.neighbors, self.next, self.expected)
+    }
+}
+
+/// Panic unless `rule` turns `center` surrounded by `neighbors` into
+/// `expected`.
+#[track_caller]
+pub fn assert_transition(rule: &dyn Rule, center: u8, neighbors: &Neighbors, expected: u8) {
+    let next = rule.next_state(center, neighbors);
+    if next != expected {
+        panic!("{}", Violation { center, neighbors: *neighbors, next, expected });
+    }
+}
+
+/// A dead cell among dead cells stays dead, so an empty universe stays
+/// empty.
+pub fn check_quiescent(rule: &dyn Rule) -> Result<(), Violation> {
+    let next = rule.next_state(0, &[0; 8]);
+    if next != 0 {
+        return Err(Violation { center: 0, neighbors: [0; 8], next, expected: 0 });
+    }
+    Ok(())
+}
+
+/// Rotating or mirroring the neighbors never changes the next state, so
+/// patterns behave the same in every direction.
+pub fn check_isotropic(rule: &dyn Rule) -> Result<(), Violation> {
+    for_each_transition(rule.states(), |center, neighbors| {
+        let expected = rule.next_state(center, neighbors);
+        for turned in symmetries(neighbors) {
+            let next = rule.next_state(center, &turned);
+            if next != expected {
+                return Err(Violation { center, neighbors: turned, next, expected });
+            }
+        }
+        Ok(())
+    })
+}
+
+fn for_each_transition<F>(states: usize, mut check: F) -> Result<(), Violation>
+where F: FnMut(u8, &Neighbors) -> Result<(), Violation> {
+    let states = states.clamp(1, 256) as u128;
+    for center in 0..states {
+        for mut combination in 0..states.pow(8) {
+            let mut neighbors = [0; 8];
+            for slot in neighbors.iter_mut() {
+                *slot = (combination % states) as u8;
+                combination /= states;
+            }
+            check(center as u8, &neighbors)?;
+        }
+    }
+    Ok(())
+}
+
+/// The seven other arrangements of the neighbors under quarter turns and
+/// mirroring, `Neighbors` going round from the north.
+fn symmetries(neighbors: &Neighbors) -> impl Iterator<Item = Neighbors> + '_ {
+    (1..8).map(move |symmetry| {
+        let (turns, mirrored) = (symmetry % 4, symmetry >= 4);
+        let mut turned = [0; 8];
+        for (i, slot) in turned.iter_mut().enumerate() {
+            let source = if mirrored { (8 - i) % 8 } else { i };
+            *slot = neighbors[(source + 8 - 2 * turns) % 8];
+        }
+        turned
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_transition, check_isotropic, check_quiescent};
+    use crate::game_of_life::rules::{self, Neighbors, Rule};
+
+    /// Born from a live cell to the north only.
+    struct North;
+
+    impl Rule for North {
+        fn states(&self) -> usize {
+            2
+        }
+
+        fn next_state(&self, _: u8, neighbors: &Neighbors) -> u8 {
+            neighbors[0]
+        }
+    }
+
+    #[test]
+    fn test_checks() {
+        let life = rules::parse("B3/S23").unwrap();
+        assert_transition(life.as_ref(), 0, &[1, 1, 1, 0, 0, 0, 0, 0], 1);
+        assert!(check_quiescent(life.as_ref()).is_ok());
+        assert!(check_isotropic(life.as_ref()).is_ok());
+        assert!(check_isotropic(rules::parse("B2/S/C3").unwrap().as_ref()).is_ok());
+
+        let violation = check_isotropic(&North).unwrap_err();
+        assert_eq!((violation.next, violation.expected), (0, 1));
+        assert!(check_quiescent(&North).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "state 0 with neighbors")]
+    fn test_assert_transition() {
+        assert_transition(&North, 0, &[0, 1, 0, 0, 0, 0, 0, 0], 1);
+    }
+
+}
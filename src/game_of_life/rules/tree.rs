@@ -0,0 +1,217 @@
+use super::golly;
+use super::{Neighbors, Rule};
+use crate::game_of_life::ParseError;
+
+/// Indexes in `Neighbors` of the cells a Moore tree descends through, from
+/// the root: NW, NE, SW, SE, N, W, E, S, the center being last.
+const MOORE_ORDER: [usize; 8] = [7, 1, 5, 3, 0, 6, 2, 4];
+/// Same for the von Neumann neighborhood: N, W, E, S.
+const VON_NEUMANN_ORDER: [usize; 4] = [0, 6, 2, 4];
+
+/// A rule loaded from the `@TREE` section of a Golly `.rule` file.
+///
+/// The tree is a decision diagram: each level picks a child from the state
+/// of one neighbor, the last level gives the next state from the state of the
+/// cell itself. Internal nodes are laid out back to back in one array, each
+/// entry being the offset of the chosen child, so evaluating a cell is a
+/// chain of dependent loads in a compact buffer. The bottom level lives in a
+/// separate byte array.
+pub struct TreeRule {
+    name: String,
+    states: usize,
+    order: &'static [usize],
+    root: usize,
+    nodes: Vec<u32>,
+    leaves: Vec<u8>,
+}
+
+impl TreeRule {
+
+    /// Load the `@TREE` section of a `.rule` file, named after its `@RULE` line.
+    pub fn from_rule_file(text: &str) -> Result<Self, ParseError> {
+        let tree = golly::section(text, "TREE").ok_or_else(|| ParseError::end_of(text, "no @TREE section in rule file"))?;
+        let name = golly::section(text, "RULE").map_or("", |section| section.argument);
+        TreeRule::parse(name, &tree.lines).map_err(|error| error.after_line(tree.line))
+    }
+
+    /// Build the rule from the lines of a `@TREE` section, errors counting
+    /// lines from the first one given.
+    pub fn parse(name: &str, lines: &[&str]) -> Result<Self, ParseError> {
+        let mut states = None;
+        let mut order = None;
+        let mut declared_nodes = None;
+        // Level and offset, in `nodes` or `leaves`, of every node read so far.
+        let mut read: Vec<(usize, usize)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut leaves = Vec::new();
+
+        let mut read_line = |line: &str| -> Result<(), &'static str> {
+            if line.is_empty() {
+                return Ok(());
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim().parse::<usize>().map_err(|_| "invalid tree header value")?;
+                match key.trim() {
+                    "num_states" if (2..=256).contains(&value) => states = Some(value),
+                    "num_states" => return Err("num_states must be between 2 and 256"),
+                    "num_neighbors" => order = Some(match value {
+                        8 => &MOORE_ORDER[..],
+                        4 => &VON_NEUMANN_ORDER[..],
+                        _ => return Err("num_neighbors must be 4 or 8"),
+                    }),
+                    "num_nodes" => declared_nodes = Some(value),
+                    _ => return Err("unknown tree header"),
+                }
+                return Ok(());
+            }
+
+            let states = states.ok_or("num_states must be declared before nodes")?;
+            let mut numbers = line.split_whitespace().map(|number| number.parse::<usize>().map_err(|_| "invalid tree node"));
+            let level = numbers.next().ok_or("invalid tree node")??;
+            let entries = numbers.collect::<Result<Vec<_>, _>>()?;
+            if entries.len() != states {
+                return Err("tree node must have one entry per state");
+            }
+
+            if level == 1 {
+                if entries.iter().any(|&state| state >= states) {
+                    return Err("state out of range in tree node");
+                }
+                read.push((1, leaves.len()));
+                leaves.extend(entries.iter().map(|&state| state as u8));
+            } else {
+                read.push((level, nodes.len()));
+                for child in entries {
+                    match read.get(child) {
+                        Some(&(child_level, offset)) if child_level + 1 == level => nodes.push(offset as u32),
+                        _ => return Err("tree node child must be an earlier node one level below"),
+                    }
+                }
+            }
+            Ok(())
+        };
+        for (index, line) in lines.iter().enumerate() {
+            read_line(golly::strip_comment(line)).map_err(|message| golly::line_error(lines, index, message))?;
+        }
+
+        // The whole tree is read, errors are at its end.
+        let missing = |message| golly::line_error(lines, lines.len(), message);
+        let states = states.ok_or_else(|| missing("missing num_states"))?;
+        let order = order.ok_or_else(|| missing("missing num_neighbors"))?;
+        if declared_nodes.is_some_and(|count| count != read.len()) {
+            return Err(missing("num_nodes does not match the number of nodes"));
+        }
+        // The root is the last node and decides on every neighbor.
+        let root = match read.last() {
+            Some(&(level, offset)) if level == order.len() + 1 => offset,
+            _ => return Err(missing("tree root must be the last node, at the top level")),
+        };
+
+        Ok(TreeRule {
+            name: name.to_string(),
+            states,
+            order,
+            root,
+            nodes,
+            leaves,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+}
+
+impl Rule for TreeRule {
+
+    fn states(&self) -> usize {
+        self.states
+    }
+
+    fn next_state(&self, cell: u8, neighbors: &Neighbors) -> u8 {
+        if neighbors.iter().chain([cell].iter()).any(|&state| state as usize >= self.states) {
+            return cell;
+        }
+        let mut offset = self.root;
+        for &index in self.order {
+            offset = self.nodes[offset + neighbors[index] as usize] as usize;
+        }
+        self.leaves[offset + cell as usize]
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Rule, TreeRule, MOORE_ORDER};
+
+    fn life(cell: u8, neighbors: &[u8]) -> u8 {
+        let alive = neighbors.iter().filter(|&&n| n == 1).count();
+        (alive == 3 || (cell == 1 && alive == 2)) as u8
+    }
+
+    /// Build the tree of Life the way Golly's generator does, sharing equal
+    /// nodes.
+    fn life_tree() -> String {
+        fn build(inputs: &mut Vec<u8>, lines: &mut Vec<String>, known: &mut HashMap<String, usize>) -> usize {
+            let line = if inputs.len() == 8 {
+                format!("1 {} {}", life(0, inputs), life(1, inputs))
+            } else {
+                let mut children = Vec::new();
+                for state in 0..2 {
+                    inputs.push(state);
+                    children.push(build(inputs, lines, known));
+                    inputs.pop();
+                }
+                format!("{} {} {}", 9 - inputs.len(), children[0], children[1])
+            };
+            *known.entry(line.clone()).or_insert_with(|| {
+                lines.push(line);
+                lines.len() - 1
+            })
+        }
+
+        let mut lines = Vec::new();
+        build(&mut Vec::new(), &mut lines, &mut HashMap::new());
+        format!("@RULE LifeTree\n@TREE\nnum_states=2\nnum_neighbors=8\nnum_nodes={}\n{}\n", lines.len(), lines.join("\n"))
+    }
+
+    #[test]
+    fn test_life_tree() {
+        let rule = TreeRule::from_rule_file(&life_tree()).unwrap();
+        assert_eq!(rule.name(), "LifeTree");
+
+        for bits in 0..512u32 {
+            let cell = (bits >> 8) as u8 & 1;
+            let mut neighbors = [0u8; 8];
+            for (i, neighbor) in neighbors.iter_mut().enumerate() {
+                *neighbor = (bits >> i) as u8 & 1;
+            }
+            let ordered: Vec<u8> = MOORE_ORDER.iter().map(|&i| neighbors[i]).collect();
+            assert_eq!(rule.next_state(cell, &neighbors), life(cell, &ordered));
+        }
+    }
+
+    #[test]
+    fn test_invalid_trees() {
+        let header = "num_states=2\nnum_neighbors=4\n";
+        let parse = |body: &str| {
+            let text = format!("@TREE\n{}{}", header, body);
+            TreeRule::from_rule_file(&text).map(|_| ())
+        };
+        // Root below the top level.
+        assert!(parse("1 0 1\n2 0 0").is_err());
+        assert_eq!(parse("1 0 1\n2 0 0").unwrap_err().line, 5);
+        // Child on the wrong level.
+        assert!(parse("1 0 1\n2 0 0\n3 0 0").is_err());
+        // Missing entry.
+        assert!(parse("1 0").is_err());
+        // The smallest valid von Neumann tree: everything dies.
+        assert!(parse("1 0 0\n2 0 0\n3 1 1\n4 2 2\n5 3 3").is_ok());
+    }
+
+}
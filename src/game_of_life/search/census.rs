@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::game_of_life::{ParseError, Pattern, UniverseError};
+
+/// Occurrence counts of search results, kept in an append-only file.
+///
+/// Every line of the file is `<occurrences> <key>`, the counts of a key
+/// add up. The file is read once on open, then counts are looked up in
+/// memory and every record appends a line, so a crash loses nothing but
+/// the last write.
+pub struct Census {
+    file: File,
+    counts: BTreeMap<String, u64>,
+}
+
+impl Census {
+
+    /// Open the census at `path`, creating it when missing.
+    pub fn open<P>(path: P) -> Result<Self, UniverseError>
+    where P: AsRef<Path> {
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|_| UniverseError::Io("census file can not be opened"))?;
+        let mut counts = BTreeMap::new();
+        for (index, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line.map_err(|_| UniverseError::Io("census file can not be read"))?;
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message| ParseError::at(message, index + 1, &line, 0);
+            let (occurrences, key) = line.split_once(' ').ok_or_else(|| error("census line without a key"))?;
+            let occurrences: u64 = occurrences.parse().map_err(|_| error("invalid census count"))?;
+            *counts.entry(key.to_string()).or_insert(0) += occurrences;
+        }
+        Ok(Census { file, counts })
+    }
+
+    /// Add `occurrences` to the count of `key`, keys are a single line.
+    pub fn record(&mut self, key: &str, occurrences: u64) -> Result<(), UniverseError> {
+        if key.is_empty() || key.contains('\n') {
+            return Err(UniverseError::InvalidArgument("census keys must be a non empty single line"));
+        }
+        writeln!(self.file, "{} {}", occurrences, key).map_err(|_| UniverseError::Io("census file can not be written"))?;
+        *self.counts.entry(key.to_string()).or_insert(0) += occurrences;
+        Ok(())
+    }
+
+    pub fn count(&self, key: &str) -> u64 {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// Number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The `n` most common keys, most common first.
+    pub fn top(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut entries: Vec<(&str, u64)> = self.counts.iter().map(|(key, &count)| (key.as_str(), count)).collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        entries.truncate(n);
+        entries
+    }
+
+}
+
+/// A key the same for a pattern in any position, rotation or reflection:
+/// the smallest RLE of its eight orientations, cropped to its live cells.
+pub fn canonical_key(pattern: &Pattern) -> String {
+    let bounds = match pattern.bounding_box() {
+        Some(bounds) => bounds,
+        None => return "empty".to_string(),
+    };
+    let (x0, y0) = (bounds.origin.x as usize, bounds.origin.y as usize);
+    let (width, height) = (bounds.size.width, bounds.size.height);
+    let cell = |x: usize, y: usize| pattern.get(x0 + x, y0 + y).unwrap_or(0);
+    (0..8)
+        .map(|orientation| {
+            let transposed = orientation & 4 != 0;
+            let (w, h) = if transposed { (height, width) } else { (width, height) };
+            let mut oriented = Pattern::new(w, h);
+            for y in 0..h {
+                for x in 0..w {
+                    let (a, b) = if transposed { (y, x) } else { (x, y) };
+                    let sx = if orientation & 1 != 0 { width - 1 - a } else { a };
+                    let sy = if orientation & 2 != 0 { height - 1 - b } else { b };
+                    // Both orientations have the same cells.
+                    let _ = oriented.set(x, y, cell(sx, sy));
+                }
+            }
+            let body: String = oriented.to_rle().lines().skip(1).collect();
+            format!("{}x{}:{}", w, h, body)
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{canonical_key, Census};
+    use crate::game_of_life::{Pattern, UniverseError};
+
+    #[test]
+    fn test_census_reopens() {
+        let path = std::env::temp_dir().join(format!("census-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let glider = canonical_key(&Pattern::from_ascii_art(".O.\n..O\nOOO", "O"));
+        let mirrored = canonical_key(&Pattern::from_ascii_art("....\n.OOO\n.O..\n..O.", "O"));
+        assert_eq!(glider, mirrored);
+
+        {
+            let mut census = Census::open(&path).unwrap();
+            census.record(&glider, 3).unwrap();
+            census.record("block", 10).unwrap();
+            assert!(census.record("two\nlines", 1).is_err());
+        }
+        let mut census = Census::open(&path).unwrap();
+        census.record(&glider, 2).unwrap();
+        assert_eq!(census.count(&glider), 5);
+        assert_eq!(census.top(1), vec![("block", 10)]);
+        assert_eq!(census.len(), 2);
+
+        fs::write(&path, "1 block\nmany block\n").unwrap();
+        match Census::open(&path) {
+            Err(UniverseError::Parse(error)) => assert_eq!((error.message, error.line), ("invalid census count", 2)),
+            _ => panic!("a bad count must be a parse error"),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+}
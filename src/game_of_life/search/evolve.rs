@@ -0,0 +1,120 @@
+use super::Evaluator;
+use crate::game_of_life::{Pattern, Progress, Rng, Size, UniverseError, Xoshiro256};
+
+/// Settings of a genetic search.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvolveConfig {
+    /// Size of every candidate pattern.
+    pub size: Size,
+    /// Probability of a cell to be alive in the first candidates.
+    pub density: f64,
+    /// Candidates in every round.
+    pub population: usize,
+    /// Rounds of selection.
+    pub rounds: usize,
+    /// Candidates competing for every parent slot.
+    pub tournament: usize,
+    /// Probability for a cell of a child to be flipped.
+    pub mutation_rate: f64,
+    /// Best candidates copied as is to the next round.
+    pub elites: usize,
+    /// Seed of every random choice, the same seed finds the same patterns.
+    pub seed: u64,
+}
+
+impl Default for EvolveConfig {
+    fn default() -> Self {
+        EvolveConfig {
+            size: Size::new(5, 5),
+            density: 0.4,
+            population: 24,
+            rounds: 20,
+            tournament: 3,
+            mutation_rate: 0.04,
+            elites: 2,
+            seed: 0,
+        }
+    }
+}
+
+/// Breed patterns scoring high with `evaluator`, a lifespan fitness looks
+/// for methuselahs.
+///
+/// Every round, candidates are scored, the elites kept, and the rest of the
+/// next round are children of two parents picked by tournament: rows from
+/// one parent above a random cut and from the other below, then mutated.
+/// Returns the last round, scored and best first.
+pub fn evolve<P>(evaluator: &Evaluator, config: &EvolveConfig, progress: &mut P) -> Result<Vec<(f64, Pattern)>, UniverseError>
+where P: Progress + ?Sized {
+    if config.population == 0 || config.tournament == 0 {
+        return Err(UniverseError::InvalidArgument("population and tournament must be greater than 0"));
+    }
+    let mut rng = Xoshiro256::new(config.seed);
+    let mut candidates: Vec<Pattern> = (0..config.population).map(|_| random_pattern(config, &mut rng)).collect();
+    for round in 0..config.rounds {
+        let ranked = evaluator.rank(candidates)?;
+        progress.report_steps(round, config.rounds, &format!("round {}, best score {}", round, ranked[0].0));
+
+        candidates = ranked.iter().take(config.elites.min(config.population)).map(|(_, pattern)| pattern.clone()).collect();
+        while candidates.len() < config.population {
+            let first = tournament(&ranked, config.tournament, &mut rng);
+            let second = tournament(&ranked, config.tournament, &mut rng);
+            let child = crossover(first, second, &mut rng);
+            candidates.push(child.mutate(config.mutation_rate, rng.next_u64()));
+        }
+    }
+    let ranked = evaluator.rank(candidates)?;
+    progress.report(1.0, "done");
+    Ok(ranked)
+}
+
+fn random_pattern(config: &EvolveConfig, rng: &mut Xoshiro256) -> Pattern {
+    let mut pattern = Pattern::new(config.size.width, config.size.height);
+    for cell in pattern.cells_mut() {
+        *cell = rng.chance(config.density) as u8;
+    }
+    pattern
+}
+
+/// The best of `size` candidates drawn at random.
+fn tournament<'a>(ranked: &'a [(f64, Pattern)], size: usize, rng: &mut Xoshiro256) -> &'a Pattern {
+    // Ranked best first, the smallest index wins.
+    let winner = (0..size).map(|_| rng.below(ranked.len())).min().unwrap_or(0);
+    &ranked[winner].1
+}
+
+/// Rows of `first` above a random cut, and of `second` from it.
+fn crossover(first: &Pattern, second: &Pattern, rng: &mut Xoshiro256) -> Pattern {
+    let cut = rng.below(first.height() + 1) * first.width();
+    let mut child = first.clone();
+    child.cells_mut()[cut..].copy_from_slice(&second.cells()[cut..]);
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evolve, EvolveConfig};
+    use crate::game_of_life::search::{Evaluator, Fitness};
+    use crate::game_of_life::{rules, NoProgress, Size};
+
+    #[test]
+    fn test_evolve_lifespan() {
+        let life = rules::parse("B3/S23").unwrap();
+        let mut evaluator = Evaluator::new(life.as_ref(), 40, Fitness::Lifespan);
+        evaluator.margin = 10;
+        let config = EvolveConfig { size: Size::new(3, 3), population: 8, rounds: 4, seed: 5, ..EvolveConfig::default() };
+
+        let mut reports = Vec::new();
+        let ranked = evolve(&evaluator, &config, &mut |fraction: f64, _: &str| reports.push(fraction)).unwrap();
+        assert_eq!(ranked.len(), 8);
+        assert!(ranked.windows(2).all(|pair| pair[0].0 >= pair[1].0));
+        assert_eq!(reports.len(), 5);
+        assert_eq!(reports.last(), Some(&1.0));
+
+        // Elites survive, so the best never gets worse than the first round's.
+        let first = evolve(&evaluator, &EvolveConfig { rounds: 0, ..config.clone() }, &mut NoProgress).unwrap();
+        assert!(ranked[0].0 >= first[0].0);
+        assert_eq!(evolve(&evaluator, &config, &mut NoProgress).unwrap(), ranked);
+    }
+
+}
@@ -0,0 +1,114 @@
+use crate::game_of_life::rules::Rule;
+use crate::game_of_life::{Pattern, Universe, UniverseError};
+
+/// What makes a pattern better than another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Fitness {
+    /// Generations before the pattern dies or settles into still lifes and
+    /// blinkers, the measure of a methuselah.
+    Lifespan,
+    /// Population after the last generation.
+    FinalPopulation,
+    /// Largest population reached.
+    PeakPopulation,
+}
+
+/// Scores patterns by running them in a universe of their own.
+pub struct Evaluator<'a> {
+    pub rule: &'a dyn Rule,
+    /// Generations every pattern is run for.
+    pub generations: u64,
+    /// Dead cells around the pattern, cells growing past them are lost.
+    pub margin: usize,
+    pub fitness: Fitness,
+}
+
+impl<'a> Evaluator<'a> {
+
+    pub fn new(rule: &'a dyn Rule, generations: u64, fitness: Fitness) -> Self {
+        Evaluator { rule, generations, margin: 32, fitness }
+    }
+
+    /// Score of a pattern, higher is better.
+    pub fn score(&self, pattern: &Pattern) -> Result<f64, UniverseError> {
+        let margin = self.margin;
+        let mut universe = Universe::with_states(pattern.width() + 2 * margin, pattern.height() + 2 * margin, self.rule.states())?;
+        universe.stamp(pattern, margin, margin)?;
+
+        let mut peak = universe.population();
+        // The last two generations, to notice period 1 and 2 cycles.
+        let mut previous: [Vec<u8>; 2] = [Vec::new(), Vec::new()];
+        for generation in 0..self.generations {
+            let cells = cells(&universe)?;
+            if self.fitness == Fitness::Lifespan {
+                if universe.population() == 0 {
+                    return Ok(generation as f64);
+                }
+                // Settled one or two generations ago.
+                if let Some(period) = previous.iter().rev().position(|cells_before| *cells_before == cells) {
+                    return Ok((generation - period as u64 - 1) as f64);
+                }
+            }
+            previous = [std::mem::take(&mut previous[1]), cells];
+            universe.step(self.rule)?;
+            peak = peak.max(universe.population());
+        }
+        Ok(match self.fitness {
+            Fitness::Lifespan => self.generations as f64,
+            Fitness::FinalPopulation => universe.population() as f64,
+            Fitness::PeakPopulation => peak as f64,
+        })
+    }
+
+    /// Score every pattern, best first.
+    pub fn rank(&self, patterns: Vec<Pattern>) -> Result<Vec<(f64, Pattern)>, UniverseError> {
+        let mut ranked = patterns
+            .into_iter()
+            .map(|pattern| Ok((self.score(&pattern)?, pattern)))
+            .collect::<Result<Vec<_>, UniverseError>>()?;
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(ranked)
+    }
+
+    /// Rank `count` mutants of `pattern`, mutant `i` drawn from seed
+    /// `seed + i`.
+    pub fn rank_mutants(&self, pattern: &Pattern, count: usize, rate: f64, seed: u64) -> Result<Vec<(f64, Pattern)>, UniverseError> {
+        self.rank((0..count as u64).map(|i| pattern.mutate(rate, seed.wrapping_add(i))).collect())
+    }
+
+}
+
+fn cells(universe: &Universe) -> Result<Vec<u8>, UniverseError> {
+    universe.bounds().points().map(|point| universe.get_cell_at(point)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Evaluator, Fitness};
+    use crate::game_of_life::{rules, Pattern};
+
+    #[test]
+    fn test_scores() {
+        let life = rules::parse("B3/S23").unwrap();
+        let mut evaluator = Evaluator::new(life.as_ref(), 60, Fitness::Lifespan);
+        evaluator.margin = 8;
+        let block = Pattern::from_ascii_art("OO\nOO", "O");
+        let blinker = Pattern::from_ascii_art("OOO", "O");
+        let dot = Pattern::from_ascii_art("O", "O");
+        // The pentomino that turns into a pre-block, then a block.
+        let pentomino = Pattern::from_ascii_art("OO\nO.\nOO", "O");
+        assert_eq!(evaluator.score(&block).unwrap(), 0.0);
+        assert_eq!(evaluator.score(&blinker).unwrap(), 0.0);
+        assert_eq!(evaluator.score(&dot).unwrap(), 1.0);
+
+        let ranked = evaluator.rank(vec![block.clone(), pentomino.clone(), dot]).unwrap();
+        assert_eq!(ranked[0].1, pentomino);
+        assert!(ranked[0].0 > 1.0);
+
+        evaluator.fitness = Fitness::PeakPopulation;
+        assert_eq!(evaluator.score(&blinker).unwrap(), 3.0);
+        assert_eq!(evaluator.rank_mutants(&block, 4, 0.5, 1).unwrap().len(), 4);
+    }
+
+}
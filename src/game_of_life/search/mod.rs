@@ -0,0 +1,10 @@
+//! Automatic search for interesting patterns.
+
+pub mod census;
+pub mod evolve;
+pub mod fitness;
+pub mod mutation;
+
+pub use census::{canonical_key, Census};
+pub use evolve::{evolve, EvolveConfig};
+pub use fitness::{Evaluator, Fitness};
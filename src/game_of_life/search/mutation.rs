@@ -0,0 +1,56 @@
+use crate::game_of_life::{Pattern, Rng, Xoshiro256};
+
+impl Pattern {
+
+    /// A copy with every cell of the bounding box flipped with probability
+    /// `rate`, the whole pattern when it is empty.
+    ///
+    /// Live cells of any state die, dead cells are born in state 1. The
+    /// same seed gives the same mutant.
+    pub fn mutate(&self, rate: f64, seed: u64) -> Pattern {
+        let mut rng = Xoshiro256::new(seed);
+        let mut mutant = self.clone();
+        let area = self.bounding_box().map_or((0, 0, self.width(), self.height()), |bounds| {
+            (bounds.origin.x as usize, bounds.origin.y as usize, bounds.size.width, bounds.size.height)
+        });
+        let (x0, y0, width, height) = area;
+        for y in y0..y0 + height {
+            for x in x0..x0 + width {
+                if rng.chance(rate) {
+                    let index = y * self.width() + x;
+                    let cells = mutant.cells_mut();
+                    cells[index] = (cells[index] == 0) as u8;
+                }
+            }
+        }
+        mutant
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_of_life::Pattern;
+
+    #[test]
+    fn test_mutate() {
+        let pattern = Pattern::from_ascii_art(
+            "
+            ......
+            .OO...
+            ..O...
+            ......
+            ",
+            "O",
+        );
+        assert_eq!(pattern.mutate(0.0, 1), pattern);
+        assert_eq!(pattern.mutate(0.5, 9), pattern.mutate(0.5, 9));
+
+        // Cells outside the bounding box are never touched.
+        let flipped = pattern.mutate(1.0, 1);
+        assert_eq!(flipped.population(), 1);
+        assert_eq!(flipped.get(1, 2).unwrap(), 1);
+        assert_eq!(Pattern::new(3, 2).mutate(1.0, 1).population(), 6);
+    }
+
+}
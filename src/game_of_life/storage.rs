@@ -0,0 +1,161 @@
+//! Named blobs, kept in memory or save files.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::UniverseError;
+
+/// Where persistence features keep their data: a flat namespace of named
+/// blobs.
+///
+/// Names are non empty and made of letters, digits, `-`, `_` and `.`, so
+/// every backend can store them as they are.
+pub trait Storage {
+    /// The blob named `name`, `None` when there is none.
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, UniverseError>;
+
+    /// Write a blob, replacing any blob of the same name.
+    fn put(&mut self, name: &str, data: &[u8]) -> Result<(), UniverseError>;
+
+    /// Names of every blob, sorted.
+    fn list(&self) -> Result<Vec<String>, UniverseError>;
+
+    /// Remove a blob, deleting a missing blob is not an error.
+    fn delete(&mut self, name: &str) -> Result<(), UniverseError>;
+}
+
+fn check_name(name: &str) -> Result<(), UniverseError> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+    if name.is_empty() || name.starts_with('.') || !name.chars().all(valid) {
+        return Err(UniverseError::InvalidArgument("invalid storage name"));
+    }
+    Ok(())
+}
+
+/// Blobs kept in memory, lost when dropped.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    blobs: BTreeMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, UniverseError> {
+        check_name(name)?;
+        Ok(self.blobs.get(name).cloned())
+    }
+
+    fn put(&mut self, name: &str, data: &[u8]) -> Result<(), UniverseError> {
+        check_name(name)?;
+        self.blobs.insert(name.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<String>, UniverseError> {
+        Ok(self.blobs.keys().cloned().collect())
+    }
+
+    fn delete(&mut self, name: &str) -> Result<(), UniverseError> {
+        check_name(name)?;
+        self.blobs.remove(name);
+        Ok(())
+    }
+}
+
+/// Blobs kept as the files of a directory, created when missing.
+#[derive(Clone, Debug)]
+pub struct DirectoryStorage {
+    directory: PathBuf,
+}
+
+impl DirectoryStorage {
+
+    pub fn open<P>(directory: P) -> Result<Self, UniverseError>
+    where P: Into<PathBuf> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|_| UniverseError::Io("could not create the storage directory"))?;
+        Ok(DirectoryStorage { directory })
+    }
+
+}
+
+impl Storage for DirectoryStorage {
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, UniverseError> {
+        check_name(name)?;
+        match fs::read(self.directory.join(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(_) => Err(UniverseError::Io("could not read from the storage directory")),
+        }
+    }
+
+    fn put(&mut self, name: &str, data: &[u8]) -> Result<(), UniverseError> {
+        check_name(name)?;
+        // Write then rename, so a crash never leaves half a blob.
+        let temporary = self.directory.join(format!(".{}.tmp", name));
+        fs::write(&temporary, data).map_err(|_| UniverseError::Io("could not write to the storage directory"))?;
+        fs::rename(&temporary, self.directory.join(name)).map_err(|_| UniverseError::Io("could not write to the storage directory"))
+    }
+
+    fn list(&self) -> Result<Vec<String>, UniverseError> {
+        let entries = fs::read_dir(&self.directory).map_err(|_| UniverseError::Io("could not list the storage directory"))?;
+        let mut names = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|_| UniverseError::Io("could not list the storage directory"))?;
+            if let Some(name) = entry.file_name().to_str() {
+                if check_name(name).is_ok() && entry.path().is_file() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn delete(&mut self, name: &str) -> Result<(), UniverseError> {
+        check_name(name)?;
+        match fs::remove_file(self.directory.join(name)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(UniverseError::Io("could not delete from the storage directory")),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirectoryStorage, MemoryStorage, Storage};
+
+    fn exercise(storage: &mut dyn Storage) {
+        assert_eq!(storage.get("slot-1").unwrap(), None);
+        storage.put("slot-1", b"first").unwrap();
+        storage.put("autosave.rle", b"x = 1, y = 1\no!").unwrap();
+        storage.put("slot-1", b"second").unwrap();
+        assert_eq!(storage.get("slot-1").unwrap().as_deref(), Some(&b"second"[..]));
+        assert_eq!(storage.list().unwrap(), vec!["autosave.rle", "slot-1"]);
+        storage.delete("slot-1").unwrap();
+        storage.delete("slot-1").unwrap();
+        assert_eq!(storage.list().unwrap(), vec!["autosave.rle"]);
+        assert!(storage.put("../escape", b"").is_err());
+        assert!(storage.get("").is_err());
+    }
+
+    #[test]
+    fn test_backends() {
+        exercise(&mut MemoryStorage::new());
+
+        let directory = std::env::temp_dir().join(format!("storage-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        exercise(&mut DirectoryStorage::open(&directory).unwrap());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+}
@@ -0,0 +1,89 @@
+use super::{Point, Universe};
+
+/// Mirrors applied to every `Universe::set_cell`, to draw symmetric seeds.
+///
+/// Mirror axes go through the center cell of the universe's symmetry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Symmetry {
+    #[default]
+    None,
+    /// Left and right halves mirror each other.
+    Horizontal,
+    /// Top and bottom halves mirror each other.
+    Vertical,
+    /// Both of the above.
+    FourFold,
+    /// Four-fold, mirrored along the diagonals too.
+    EightFold,
+}
+
+impl Symmetry {
+
+    /// Every image of `offset`, a position relative to the center, itself
+    /// included.
+    pub fn images(&self, offset: Point) -> Vec<Point> {
+        let Point { x, y } = offset;
+        let mut images = match self {
+            Symmetry::None => vec![offset],
+            Symmetry::Horizontal => vec![offset, Point::new(-x, y)],
+            Symmetry::Vertical => vec![offset, Point::new(x, -y)],
+            Symmetry::FourFold => vec![offset, Point::new(-x, y), Point::new(x, -y), -offset],
+            Symmetry::EightFold => vec![
+                offset,
+                Point::new(-x, y),
+                Point::new(x, -y),
+                -offset,
+                Point::new(y, x),
+                Point::new(-y, x),
+                Point::new(y, -x),
+                Point::new(-y, -x),
+            ],
+        };
+        // Cells on an axis are their own image.
+        images.sort();
+        images.dedup();
+        images
+    }
+
+}
+
+impl Universe {
+
+    /// Grid positions of the images of (x, y), those outside of the grid
+    /// are dropped.
+    pub(crate) fn mirrored(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let (symmetry, center) = self.symmetry();
+        symmetry
+            .images(Point::new(x as i64, y as i64) - center)
+            .into_iter()
+            .filter_map(|image| (image + center).to_unsigned())
+            .filter(|&(x, y)| x < self.width() && y < self.height())
+            .collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symmetry;
+    use crate::game_of_life::{Point, Universe};
+
+    #[test]
+    fn test_mirrored_edits() {
+        let mut universe = Universe::new(7, 7).unwrap();
+        universe.set_symmetry(Symmetry::EightFold, Point::new(3, 3));
+        universe.set_cell(4, 1, 1).unwrap();
+        assert_eq!(universe.population(), 8);
+        assert!(universe.is_alive(1, 2).unwrap() && universe.is_alive(5, 4).unwrap());
+
+        // On an axis, and mirrored out of the grid.
+        let mut universe = Universe::new(5, 5).unwrap();
+        universe.set_symmetry(Symmetry::FourFold, Point::new(1, 2));
+        universe.set_cell(1, 0, 1).unwrap();
+        assert_eq!(universe.population(), 2);
+        universe.set_cell(4, 0, 1).unwrap();
+        assert_eq!(universe.population(), 4);
+    }
+
+}
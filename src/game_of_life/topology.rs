@@ -0,0 +1,225 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::rules::{Neighborhood, Neighbors, NEIGHBOR_OFFSETS};
+use super::{ParseError, Point, Universe, UniverseError};
+
+/// What lies past the edges of a bounded universe.
+///
+/// The variants are the usual boundary conditions of the literature, and
+/// parse from their names so experiment configs can pick one: `fixed-dead`,
+/// `periodic`, `reflective` and `absorbing:<margin>`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum EdgeBehavior {
+    /// Cells outside of the grid are always dead.
+    #[default]
+    Dead,
+    /// Opposite edges are glued together, the grid is a torus.
+    Wrap,
+    /// The grid is mirrored past its edges: the cell left of column 0 is
+    /// column 0 itself.
+    Reflect,
+    /// Dead edges, and every cell less than `margin` cells from an edge is
+    /// killed each generation, so spaceships are swallowed instead of
+    /// turning into debris against the edge.
+    Absorbing { margin: usize },
+}
+
+impl EdgeBehavior {
+
+    /// Whether the cell at (x, y) is in the kill zone of absorbing edges.
+    pub fn absorbs(&self, x: usize, y: usize, width: usize, height: usize) -> bool {
+        match *self {
+            EdgeBehavior::Absorbing { margin } => {
+                x < margin || y < margin || x >= width.saturating_sub(margin) || y >= height.saturating_sub(margin)
+            }
+            _ => false,
+        }
+    }
+
+}
+
+impl fmt::Display for EdgeBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeBehavior::Dead => write!(f, "fixed-dead"),
+            EdgeBehavior::Wrap => write!(f, "periodic"),
+            EdgeBehavior::Reflect => write!(f, "reflective"),
+            EdgeBehavior::Absorbing { margin } => write!(f, "absorbing:{}", margin),
+        }
+    }
+}
+
+impl FromStr for EdgeBehavior {
+    type Err = ParseError;
+
+    /// Parse a preset name, case insensitive. `absorbing` alone has a one
+    /// cell margin.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let start = text.len() - text.trim_start().len();
+        let lowercase = text.to_ascii_lowercase();
+        let (name, argument) = match lowercase.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument)),
+            None => (lowercase.trim(), None),
+        };
+        let error_at = |message, offset| ParseError::at(message, 1, text, offset);
+        let argument_start = lowercase.find(':').map_or(text.len(), |colon| colon + 1);
+        let edges = match name {
+            "fixed-dead" | "dead" | "null" => EdgeBehavior::Dead,
+            "periodic" | "toroidal" | "torus" | "wrap" => EdgeBehavior::Wrap,
+            "reflective" | "reflect" | "mirror" => EdgeBehavior::Reflect,
+            "absorbing" => {
+                let margin = argument
+                    .map_or(Ok(1), |margin| margin.trim().parse())
+                    .map_err(|_| error_at("absorbing margin must be a number", argument_start))?;
+                return Ok(EdgeBehavior::Absorbing { margin });
+            }
+            _ => return Err(error_at("unknown boundary condition", start)),
+        };
+        match argument {
+            Some(_) => Err(error_at("only absorbing boundaries take an argument", argument_start)),
+            None => Ok(edges),
+        }
+    }
+}
+
+impl Universe {
+
+    /// Where the cell at `offset` from (x, y) is, after applying the edge
+    /// behavior. With dead edges the point can lie outside of the grid.
+    pub fn neighbor_position(&self, x: usize, y: usize, offset: Point) -> Point {
+        let point = Point::new(x as i64 + offset.x, y as i64 + offset.y);
+        let reflect = |value: i64, length: i64| {
+            // Mirror images repeat every two lengths.
+            let value = value.rem_euclid(2 * length);
+            if value < length { value } else { 2 * length - value - 1 }
+        };
+        match self.edges() {
+            EdgeBehavior::Dead | EdgeBehavior::Absorbing { .. } => point,
+            EdgeBehavior::Wrap => Point::new(
+                point.x.rem_euclid(self.width() as i64),
+                point.y.rem_euclid(self.height() as i64),
+            ),
+            EdgeBehavior::Reflect => Point::new(reflect(point.x, self.width() as i64), reflect(point.y, self.height() as i64)),
+        }
+    }
+
+    /// The neighbors of the cell at (x, y), as their grid position and
+    /// state, in Golly's order.
+    ///
+    /// Positions follow the edge behavior: with dead edges they can be
+    /// outside of the grid, and those cells are dead.
+    pub fn neighbors(
+        &self,
+        x: usize,
+        y: usize,
+        neighborhood: Neighborhood,
+    ) -> Result<impl Iterator<Item = (Point, u8)> + '_, UniverseError> {
+        self.get_cell(x, y)?;
+        Ok(neighborhood.positions().iter().map(move |&position| {
+            let point = self.neighbor_position(x, y, NEIGHBOR_OFFSETS[position]);
+            let state = match point.to_unsigned() {
+                Some((x, y)) => self.get_cell(x, y).unwrap_or(0),
+                None => 0,
+            };
+            (point, state)
+        }))
+    }
+
+    /// The Moore neighbors of (x, y), the way rules see them.
+    pub fn neighbor_states(&self, x: usize, y: usize) -> Result<Neighbors, UniverseError> {
+        let mut neighbors = [0; 8];
+        for (slot, (_, state)) in neighbors.iter_mut().zip(self.neighbors(x, y, Neighborhood::Moore)?) {
+            *slot = state;
+        }
+        Ok(neighbors)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeBehavior, Neighborhood, Point, Universe};
+    use crate::game_of_life::{rules, Pattern};
+
+    #[test]
+    fn test_dead_edges() {
+        let mut universe = Universe::new(3, 3).unwrap();
+        universe.set_cell(1, 0, 1).unwrap();
+        universe.set_cell(2, 2, 1).unwrap();
+
+        let neighbors: Vec<_> = universe.neighbors(0, 0, Neighborhood::VonNeumann).unwrap().collect();
+        assert_eq!(neighbors, vec![
+            (Point::new(0, -1), 0),
+            (Point::new(1, 0), 1),
+            (Point::new(0, 1), 0),
+            (Point::new(-1, 0), 0),
+        ]);
+        assert_eq!(universe.neighbor_states(0, 0).unwrap(), [0, 0, 1, 0, 0, 0, 0, 0]);
+        assert!(universe.neighbors(3, 0, Neighborhood::Moore).is_err());
+    }
+
+    #[test]
+    fn test_wrapped_edges() {
+        let mut universe = Universe::new(3, 3).unwrap();
+        universe.set_edges(EdgeBehavior::Wrap);
+        universe.set_cell(1, 0, 1).unwrap();
+        universe.set_cell(2, 2, 1).unwrap();
+
+        // The corner at (2, 2) is the north west neighbor of (0, 0).
+        assert_eq!(universe.neighbor_states(0, 0).unwrap(), [0, 0, 1, 0, 0, 0, 0, 1]);
+        let positions: Vec<_> = universe.neighbors(0, 0, Neighborhood::Moore).unwrap().map(|(point, _)| point).collect();
+        assert!(positions.iter().all(|point| universe.bounds().contains(*point)));
+    }
+
+    #[test]
+    fn test_reflective_edges() {
+        let mut universe = Universe::new(3, 3).unwrap();
+        universe.set_edges("reflective".parse().unwrap());
+        universe.set_cell(0, 0, 1).unwrap();
+        universe.set_cell(1, 0, 1).unwrap();
+        // North is (0, 0) itself, north east (1, 0), west (0, 0) and north
+        // west (0, 0) again.
+        assert_eq!(universe.neighbor_states(0, 0).unwrap(), [1, 1, 1, 0, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_presets() {
+        for edges in [EdgeBehavior::Dead, EdgeBehavior::Wrap, EdgeBehavior::Reflect, EdgeBehavior::Absorbing { margin: 3 }] {
+            assert_eq!(edges.to_string().parse::<EdgeBehavior>(), Ok(edges));
+        }
+        assert_eq!("Torus".parse(), Ok(EdgeBehavior::Wrap));
+        assert_eq!("absorbing".parse(), Ok(EdgeBehavior::Absorbing { margin: 1 }));
+        assert!("periodic:2".parse::<EdgeBehavior>().is_err());
+        assert!("klein".parse::<EdgeBehavior>().is_err());
+        let error = "  absorbing:x".parse::<EdgeBehavior>().unwrap_err();
+        assert_eq!((error.line, error.column), (1, 13));
+        assert_eq!(" klein".parse::<EdgeBehavior>().unwrap_err().column, 2);
+
+        let mut universe = Universe::new(6, 6).unwrap();
+        universe.set_edges(EdgeBehavior::Absorbing { margin: 1 });
+        universe.stamp(&Pattern::from_ascii_art("OO\nOO", "O"), 0, 2).unwrap();
+        universe.step(rules::parse("B3/S23").unwrap().as_ref()).unwrap();
+        assert!(!universe.is_alive(0, 2).unwrap());
+        assert!(universe.is_alive(1, 2).unwrap());
+    }
+
+    #[test]
+    fn test_absorbing_margin() {
+        let edges = EdgeBehavior::Absorbing { margin: 2 };
+        assert!(edges.absorbs(4, 2, 6, 6) && !edges.absorbs(3, 2, 6, 6));
+        // A margin past the size covers everything, without overflowing.
+        let edges = EdgeBehavior::Absorbing { margin: usize::MAX };
+        assert!(edges.absorbs(3, 3, 6, 6));
+        assert!(!EdgeBehavior::Absorbing { margin: 0 }.absorbs(5, 5, 6, 6));
+
+        let mut universe = Universe::new(4, 4).unwrap();
+        universe.set_edges(EdgeBehavior::Absorbing { margin: usize::MAX });
+        universe.set_cell(1, 1, 1).unwrap();
+        universe.step(rules::parse("B3/S23").unwrap().as_ref()).unwrap();
+        assert_eq!(universe.population(), 0);
+    }
+
+}
@@ -1,19 +1,1432 @@
-use super::{BitFieldCompatible, BitsField};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wasm_bindgen::prelude::*;
+
+use super::rules::{Generations, Neighbors, Rule, NEIGHBOR_OFFSETS};
+use super::{AutoBitsField, BitsField, BitsFieldError, EdgeBehavior, Pattern, Point, Rect, Rng, Size, State, Symmetry, UniverseError};
 
 /// A universe is a 2D grid of cells.
-/// 
+///
 /// Every cell is one of possible states (max 256 states).
 /// To represent the state of a cell, we use the minimal amount of bits.
 /// Let's say we have k possible states, then we need log2(k) bits to
 /// represent the state of a cell.
+///
+/// On top of the cells, a one bit mask marks frozen obstacle cells: walls
+/// that stay dead and are never updated by the evolution.
+///
+/// Cells are addressed either by their grid position `(x, y)`, from the top
+/// left corner, or by a signed `Point` relative to a configurable origin.
+#[wasm_bindgen]
+#[derive(Clone)]
 pub struct Universe {
     width: usize,
     height: usize,
-    cells: BitsField<u8>,
+    states: usize,
+    /// Grid position of `Point::ORIGIN`.
+    origin: Point,
+    edges: EdgeBehavior,
+    /// Generations computed since the universe was created.
+    generation: u64,
+    generation_overflow: GenerationOverflow,
+    /// Rows of `pitch` cells surrounded by cells that stay dead, so
+    /// neighbors never need bounds checks: a row above and below the grid,
+    /// `lead` cells (a byte) before each row and at least one after.
+    cells: AutoBitsField,
+    lead: usize,
+    pitch: usize,
+    frozen: AutoBitsField,
+    /// Rows with a cell changed since `clear_dirty_rows`.
+    dirty_rows: Vec<bool>,
+    /// Last state set on every cell edited since `start_capture`.
+    capture: Option<BTreeMap<(usize, usize), u8>>,
+    symmetry: Symmetry,
+    symmetry_center: Point,
+    /// Cells before the last step, edits applying to both.
+    previous: Option<AutoBitsField>,
+    /// Neighbors of a step in progress, see `begin_step`.
+    pending: Option<PendingStep>,
+}
+
+/// The first phase of a step, grid cells row by row.
+#[derive(Clone)]
+struct PendingStep {
+    neighbors: Vec<Neighbors>,
+    counts: Vec<u8>,
+}
+
+/// How a cell changed in the last step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Transition {
+    /// Same state, or a live state turning into another.
+    Unchanged,
+    /// Dead, then alive.
+    Born,
+    /// Alive, then dead.
+    Died,
 }
 
-enum InitPolicy {
+/// What stamping a pattern at some place would run into, see
+/// `Universe::can_place`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlacementReport {
+    /// Live cells of the universe the stamp would change.
+    pub overwritten: Vec<(usize, usize)>,
+    /// Frozen cells under live cells of the pattern.
+    pub frozen: Vec<(usize, usize)>,
+    /// Cells of the pattern past the edges, as pattern coordinates.
+    pub out_of_bounds: Vec<(usize, usize)>,
+}
+
+impl PlacementReport {
+
+    /// Whether the pattern fits, obstacles and live cells aside.
+    pub fn fits(&self) -> bool {
+        self.out_of_bounds.is_empty()
+    }
+
+    /// Whether stamping would neither fail nor change anything but dead
+    /// cells.
+    pub fn is_clear(&self) -> bool {
+        self.overwritten.is_empty() && self.frozen.is_empty() && self.out_of_bounds.is_empty()
+    }
+
+}
+
+/// What a step does to the generation counter at `u64::MAX`, a replay or a
+/// schedule keyed by generation must not see it wrap by surprise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum GenerationOverflow {
+    /// Refuse the step with `UniverseError::GenerationOverflow`.
+    #[default]
+    Error,
+    /// Keep counting `u64::MAX`.
+    Saturate,
+    /// Go back to 0, deltas from `generations_since` stay right.
+    Wrap,
+}
+
+/// What `Universe::stamp_with` does with the cells of a pattern past the
+/// edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ClipMode {
+    /// Drop them.
+    Clip,
+    /// Continue on the opposite edge, as on a torus.
+    Wrap,
+    /// Refuse the whole stamp with `UniverseError::PatternDoesNotFit`.
+    Error,
+}
+
+/// How `Universe::initialize` fills the grid.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum InitPolicy {
+    /// Every cell is alive with the same probability.
+    ///
+    /// Multi-state universes get live cells in state 1.
     Random{alive_probability: f64},
+    /// Cells are alive with `alive_probability` at the center of the grid,
+    /// falling off as a gaussian of deviation `sigma` cells.
     Gaussian{alive_probability: f64, sigma: f64},
+    /// Exact states, row by row.
     Custom{states: Vec<u8>},
+}
+
+/// Most bytes a universe may take, 1GB unless changed.
+static MEMORY_LIMIT: AtomicUsize = AtomicUsize::new(1 << 30);
+
+/// Largest memory in bytes a new universe may take, so a huge size fails
+/// with an error rather than aborting on allocation (wasm has 4GB at most).
+pub fn set_memory_limit(bytes: usize) {
+    MEMORY_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+pub fn memory_limit() -> usize {
+    MEMORY_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Cells in the dead byte before each row, and cells in a whole row, for
+/// rows of `width` cells.
+///
+/// Rows are whole bytes, so each one can be read as bytes from its first
+/// cell.
+fn row_layout(width: usize, bits_per_cell: usize) -> (usize, usize) {
+    let lead = 8 / bits_per_cell;
+    (lead, (lead + width + 1).div_ceil(lead) * lead)
+}
+
+/// Bits a cell of `states` states takes.
+fn bits_per_cell(states: usize) -> usize {
+    // Round the bits up to a power of two, so cells never straddle two
+    // words of the field.
+    let bits = (usize::BITS - (states - 1).leading_zeros()) as usize;
+    bits.next_power_of_two()
+}
+
+impl Universe {
+
+    /// Create a dead universe of two states cells (dead or alive).
+    pub fn new(width: usize, height: usize) -> Result<Self, UniverseError> {
+        Universe::with_states(width, height, 2)
+    }
+
+    /// Bytes a universe of this size takes while stepping, `None` when it
+    /// could not be addressed at all.
+    pub fn memory_needed(width: usize, height: usize, states: usize) -> Option<usize> {
+        let bits = bits_per_cell(states.clamp(2, 256)) as u128;
+        let pitch = (width as u128 + 2 * 8).div_ceil(8) * 8;
+        // Sizes near usize::MAX overflow even 128 bits.
+        let cells = pitch.checked_mul(height as u128 + 2)?;
+        // Cells, the next generation while stepping, the frozen cells and
+        // the dirty rows.
+        let bytes = (cells.checked_mul(bits * 2)?).div_ceil(8) + cells.div_ceil(8) + height as u128;
+        usize::try_from(bytes).ok()
+    }
+
+    /// Create a dead universe where cells can be in `states` states.
+    ///
+    /// Fails when the universe would take more than `memory_limit()`.
+    pub fn with_states(width: usize, height: usize, states: usize) -> Result<Self, UniverseError> {
+        if !(2..=256).contains(&states) {
+            return Err(UniverseError::InvalidStates(states));
+        }
+        if Universe::memory_needed(width, height, states).is_none_or(|bytes| bytes > memory_limit()) {
+            return Err(UniverseError::TooLarge);
+        }
+        let (lead, pitch) = row_layout(width, bits_per_cell(states));
+        let len = pitch * (height + 2);
+        Ok(Universe {
+            width,
+            height,
+            states,
+            origin: Point::ORIGIN,
+            edges: EdgeBehavior::default(),
+            generation: 0,
+            generation_overflow: GenerationOverflow::default(),
+            cells: BitsField::auto(bits_per_cell(states), len)?,
+            lead,
+            pitch,
+            frozen: BitsField::auto(1, len)?,
+            dirty_rows: vec![true; height],
+            capture: None,
+            symmetry: Symmetry::None,
+            symmetry_center: Point::ORIGIN,
+            previous: None,
+            pending: None,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// The rectangle covered by the grid, from (0, 0).
+    pub fn bounds(&self) -> Rect {
+        Rect::new(Point::ORIGIN, self.size())
+    }
+
+    /// Number of states a cell can be in.
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    fn index(&self, x: usize, y: usize) -> Result<usize, UniverseError> {
+        if x >= self.width || y >= self.height {
+            Err(UniverseError::OutOfBounds)
+        } else {
+            Ok(self.guarded_index(x, y))
+        }
+    }
+
+    /// Index of a grid cell in the storage, past the guard ring.
+    fn guarded_index(&self, x: usize, y: usize) -> usize {
+        (y + 1) * self.pitch + self.lead + x
+    }
+
+    fn guarded_len(&self) -> usize {
+        self.pitch * (self.height + 2)
+    }
+
+    /// Neighbor states read straight from the storage, the guard ring
+    /// standing for the dead cells past the edges.
+    ///
+    /// `index` must be the guarded index of a cell of the grid.
+    fn guarded_neighbor_states(&self, index: usize) -> Neighbors {
+        let pitch = self.pitch as i64;
+        let mut neighbors = [0; 8];
+        for (slot, offset) in neighbors.iter_mut().zip(NEIGHBOR_OFFSETS.iter()) {
+            // Neighbors of a cell of the grid are at most in the guard ring,
+            // which lies inside the storage.
+            *slot = unsafe { self.cells.get_unchecked((index as i64 + offset.y * pitch + offset.x) as usize) };
+        }
+        neighbors
+    }
+
+    /// Number of generations computed so far.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Restore the generation counter, when loading a saved run.
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    pub fn generation_overflow(&self) -> GenerationOverflow {
+        self.generation_overflow
+    }
+
+    pub fn set_generation_overflow(&mut self, policy: GenerationOverflow) {
+        self.generation_overflow = policy;
+    }
+
+    /// Generations computed since the counter read `earlier`, across a
+    /// wrap when the policy is `Wrap`.
+    pub fn generations_since(&self, earlier: u64) -> u64 {
+        match self.generation_overflow {
+            GenerationOverflow::Wrap => self.generation.wrapping_sub(earlier),
+            _ => self.generation.saturating_sub(earlier),
+        }
+    }
+
+    fn next_generation(&self) -> Result<u64, UniverseError> {
+        match self.generation_overflow {
+            GenerationOverflow::Error => self.generation.checked_add(1).ok_or(UniverseError::GenerationOverflow),
+            GenerationOverflow::Saturate => Ok(self.generation.saturating_add(1)),
+            GenerationOverflow::Wrap => Ok(self.generation.wrapping_add(1)),
+        }
+    }
+
+    /// What lies past the edges of the grid.
+    pub fn edges(&self) -> EdgeBehavior {
+        self.edges
+    }
+
+    pub fn set_edges(&mut self, edges: EdgeBehavior) {
+        self.edges = edges;
+    }
+
+    /// Mirror symmetry of edits, and its center cell.
+    pub fn symmetry(&self) -> (Symmetry, Point) {
+        (self.symmetry, self.symmetry_center)
+    }
+
+    /// Mirror edits around the grid cell `center`.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry, center: Point) {
+        self.symmetry = symmetry;
+        self.symmetry_center = center;
+    }
+
+    /// Grid position of the signed point (0, 0).
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// Move the signed coordinate system, `origin` is the grid position
+    /// point (0, 0) maps to. It does not have to be inside the grid.
+    pub fn set_origin(&mut self, origin: Point) {
+        self.origin = origin;
+    }
+
+    /// Put point (0, 0) at the center of the grid, rounding down.
+    pub fn center_origin(&mut self) {
+        self.origin = Point::new((self.width / 2) as i64, (self.height / 2) as i64);
+    }
+
+    /// Grid position of a signed point, `None` outside of the grid.
+    pub fn to_grid(&self, point: Point) -> Option<(usize, usize)> {
+        let (x, y) = (point + self.origin).to_unsigned()?;
+        if x < self.width && y < self.height {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Signed point of a grid position.
+    pub fn to_point(&self, x: usize, y: usize) -> Point {
+        Point::new(x as i64, y as i64) - self.origin
+    }
+
+    /// The grid in signed coordinates.
+    pub fn signed_bounds(&self) -> Rect {
+        self.bounds().translate(-self.origin)
+    }
+
+    pub fn get_cell(&self, x: usize, y: usize) -> Result<u8, UniverseError> {
+        let index = self.index(x, y)?;
+        Ok(self.cells.get(index)?)
+    }
+
+    pub fn is_alive(&self, x: usize, y: usize) -> Result<bool, UniverseError> {
+        Ok(State::from(self.get_cell(x, y)?).is_alive())
+    }
+
+    /// Set a cell, and its mirror images when a symmetry is set.
+    pub fn set_cell<S>(&mut self, x: usize, y: usize, state: S) -> Result<(), UniverseError>
+    where S: Into<u8> {
+        let state = state.into();
+        if self.symmetry == Symmetry::None {
+            return self.write_cell(x, y, state);
+        }
+        self.index(x, y)?;
+        for (x, y) in self.mirrored(x, y) {
+            self.write_cell(x, y, state)?;
+        }
+        Ok(())
+    }
+
+    /// Set a single cell, whatever the symmetry.
+    pub(crate) fn write_cell(&mut self, x: usize, y: usize, state: u8) -> Result<(), UniverseError> {
+        if state as usize >= self.states {
+            return Err(UniverseError::StateOutOfRange { state, states: self.states });
+        }
+        self.edit_cell(x, y, |cells, index| cells.set(index, state))
+    }
+
+    /// Change a single cell with `edit`, the dirty rows, the capture and
+    /// the cells before the last step following it.
+    fn edit_cell<F>(&mut self, x: usize, y: usize, edit: F) -> Result<(), UniverseError>
+    where F: FnOnce(&mut AutoBitsField, usize) -> Result<(), BitsFieldError> {
+        let index = self.index(x, y)?;
+        edit(&mut self.cells, index)?;
+        let state = self.cells.get(index)?;
+        self.dirty_rows[y] = true;
+        if let Some(capture) = &mut self.capture {
+            capture.insert((x, y), state);
+        }
+        // An edit is not a transition.
+        if let Some(previous) = &mut self.previous {
+            previous.set(index, state)?;
+        }
+        Ok(())
+    }
+
+    /// Kill a live cell or bring a dead one to state 1, what a click of an
+    /// editor does. Mirrored cells of a symmetric drawing take the same
+    /// state.
+    pub fn toggle_cell(&mut self, x: usize, y: usize) -> Result<(), UniverseError> {
+        if self.states == 2 && self.symmetry == Symmetry::None {
+            // One bit cells flip in place.
+            return self.edit_cell(x, y, |cells, index| cells.toggle(index));
+        }
+        let state = if self.get_cell(x, y)? == 0 { 1 } else { 0 };
+        self.set_cell(x, y, state)
+    }
+
+    /// State of a cell before the last step, its current state when the
+    /// universe never stepped.
+    pub fn previous_cell(&self, x: usize, y: usize) -> Result<u8, UniverseError> {
+        let index = self.index(x, y)?;
+        Ok(self.previous.as_ref().unwrap_or(&self.cells).get(index)?)
+    }
+
+    /// How a cell changed in the last step, for renderers to animate births
+    /// and deaths between two generations.
+    pub fn transition(&self, x: usize, y: usize) -> Result<Transition, UniverseError> {
+        match (self.previous_cell(x, y)?, self.get_cell(x, y)?) {
+            (0, 0) => Ok(Transition::Unchanged),
+            (0, _) => Ok(Transition::Born),
+            (_, 0) => Ok(Transition::Died),
+            _ => Ok(Transition::Unchanged),
+        }
+    }
+
+    pub fn get_cell_at(&self, point: Point) -> Result<u8, UniverseError> {
+        let (x, y) = self.to_grid(point).ok_or(UniverseError::OutOfBounds)?;
+        self.get_cell(x, y)
+    }
+
+    pub fn set_cell_at<S>(&mut self, point: Point, state: S) -> Result<(), UniverseError>
+    where S: Into<u8> {
+        let (x, y) = self.to_grid(point).ok_or(UniverseError::OutOfBounds)?;
+        self.set_cell(x, y, state)
+    }
+
+    /// Number of non dead cells.
+    pub fn population(&self) -> usize {
+        self.cells.len() - self.cells.count_matching(0u8)
+    }
+
+    /// Number of cells in each state, indexed by state, for charting the
+    /// compartments of multi-state rules.
+    ///
+    /// Each state is counted a whole word at a time.
+    pub fn state_histogram(&self) -> Vec<u64> {
+        let mut histogram: Vec<u64> = (0..self.states).map(|state| self.cells.count_matching(state as u8) as u64).collect();
+        // The guard ring and the row padding are dead cells out of the grid.
+        histogram[0] = (self.width * self.height) as u64 - histogram[1..].iter().sum::<u64>();
+        histogram
+    }
+
+    /// Whether storage from outside, like a snapshot, holds a valid
+    /// universe: no cell nor frozen bit past the grid, where indexes would
+    /// not map back to coordinates, and no state out of range.
+    #[cfg(feature = "serde")]
+    fn check_storage(&self) -> Result<(), &'static str> {
+        let in_grid = |index: usize| {
+            (1..=self.height).contains(&(index / self.pitch)) && (self.lead..self.lead + self.width).contains(&(index % self.pitch))
+        };
+        let mut from = 0;
+        while let Some(index) = self.cells.find_next_not(from, 0u8) {
+            if !in_grid(index) {
+                return Err("cells set in the guard ring or the padding");
+            }
+            if self.cells.get::<u8>(index).map_err(|error| error.message())? as usize >= self.states {
+                return Err("cell state out of range");
+            }
+            from = index + 1;
+        }
+        let mut from = 0;
+        while let Some(index) = self.frozen.find_next_not(from, 0u8) {
+            if !in_grid(index) {
+                return Err("frozen cells set in the guard ring or the padding");
+            }
+            from = index + 1;
+        }
+        Ok(())
+    }
+
+    /// Coordinates of the non dead cells, row by row, skipping dead words
+    /// of the storage at once: cheap on mostly dead universes.
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut from = 0;
+        std::iter::from_fn(move || {
+            // The guard ring and the row padding are dead, every live cell
+            // is in the grid.
+            let index = self.cells.find_next_not(from, 0u8)?;
+            from = index + 1;
+            Some((index % self.pitch - self.lead, index / self.pitch - 1))
+        })
+    }
+
+    pub fn is_frozen(&self, x: usize, y: usize) -> Result<bool, UniverseError> {
+        let index = self.index(x, y)?;
+        Ok(self.frozen.get::<bool>(index)?)
+    }
+
+    /// Mark or unmark a cell as a frozen obstacle, frozen cells are killed.
+    pub fn set_frozen(&mut self, x: usize, y: usize, frozen: bool) -> Result<(), UniverseError> {
+        let index = self.index(x, y)?;
+        if frozen {
+            self.dirty_rows[y] = true;
+            self.cells.set(index, State::Dead as u8)?;
+        }
+        Ok(self.frozen.set(index, frozen as u8)?)
+    }
+
+    /// Whether each row had a cell changed since the last
+    /// `clear_dirty_rows`, by a step or an edit.
+    ///
+    /// Every row is dirty in a new universe, so the first frame is drawn.
+    pub fn dirty_rows(&self) -> &[bool] {
+        &self.dirty_rows
+    }
+
+    /// Mark every row as drawn, call it once the dirty rows are rendered.
+    pub fn clear_dirty_rows(&mut self) {
+        self.dirty_rows.iter_mut().for_each(|dirty| *dirty = false);
+    }
+
+    /// Start recording the cells set from now on, to turn a drawing into a
+    /// pattern. A capture in progress starts over.
+    pub fn start_capture(&mut self) {
+        self.capture = Some(BTreeMap::new());
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    /// Stop recording, and return the edited cells as a pattern with the
+    /// grid rectangle it covers.
+    ///
+    /// Cells are in the last state they were set to, cells of the rectangle
+    /// that were not edited are dead. `None` if nothing was edited.
+    pub fn stop_capture(&mut self) -> Option<(Rect, Pattern)> {
+        let capture = self.capture.take()?;
+        let (&first, _) = capture.iter().next()?;
+        let (min, max) = capture.keys().fold((first, first), |(min, max), &(x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        });
+        let mut pattern = Pattern::new(max.0 - min.0 + 1, max.1 - min.1 + 1);
+        for (&(x, y), &state) in &capture {
+            // The pattern spans every captured cell.
+            pattern.set(x - min.0, y - min.1, state).ok()?;
+        }
+        let bounds = Rect::new(Point::from_unsigned(min.0, min.1)?, pattern.size());
+        Some((bounds, pattern))
+    }
+
+    /// Remove every obstacle.
+    pub fn clear_frozen(&mut self) -> Result<(), UniverseError> {
+        self.frozen = BitsField::auto(1, self.guarded_len())?;
+        Ok(())
+    }
+
+    /// Overwrite every cell following `policy`.
+    ///
+    /// Drawing from a seeded `Rng` makes the result reproducible. Frozen
+    /// cells stay dead.
+    pub fn initialize<R>(&mut self, policy: &InitPolicy, rng: &mut R) -> Result<(), UniverseError>
+    where R: Rng + ?Sized {
+        match policy {
+            InitPolicy::Custom{states} if states.len() != self.width * self.height => {
+                return Err(UniverseError::SizeMismatch { expected: self.width * self.height, actual: states.len() });
+            }
+            InitPolicy::Random{alive_probability} | InitPolicy::Gaussian{alive_probability, ..} if !(0.0..=1.0).contains(alive_probability) => {
+                return Err(UniverseError::InvalidPolicy("alive probability must be between 0 and 1"));
+            }
+            InitPolicy::Gaussian{sigma, ..} if !(*sigma > 0.0 && sigma.is_finite()) => {
+                return Err(UniverseError::InvalidPolicy("sigma must be a positive number"));
+            }
+            _ => {}
+        }
+        let (center_x, center_y) = ((self.width as f64 - 1.0) / 2.0, (self.height as f64 - 1.0) / 2.0);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let state = match policy {
+                    InitPolicy::Random{alive_probability} => rng.chance(*alive_probability) as u8,
+                    InitPolicy::Gaussian{alive_probability, sigma} => {
+                        let distance = (x as f64 - center_x).powi(2) + (y as f64 - center_y).powi(2);
+                        let probability = alive_probability * (-distance / (2.0 * sigma * sigma)).exp();
+                        rng.chance(probability) as u8
+                    }
+                    InitPolicy::Custom{states} => states[y * self.width + x],
+                };
+                if self.is_frozen(x, y)? {
+                    continue;
+                }
+                self.write_cell(x, y, state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace every cell by its next state under `rule`, frozen cells and
+    /// the kill zone of absorbing edges staying dead.
+    pub fn step(&mut self, rule: &dyn Rule) -> Result<(), UniverseError> {
+        self.apply_rule(rule, |universe, x, y| universe.current_neighbors(x, y))?;
+        // A step begun in two phases is abandoned.
+        self.pending = None;
+        Ok(())
+    }
+
+    /// Step Conway's Life, `B3/S23`, on a universe of two states.
+    pub fn tick(&mut self) -> Result<(), UniverseError> {
+        self.step(&Generations::life())
+    }
+
+    /// First phase of a step taken in two, for debugging a rule cell by
+    /// cell: the neighbors of every cell are read and their live counts
+    /// show in `neighbor_counts` until `finish_step`.
+    ///
+    /// Takes nine bytes per cell on top of the universe.
+    pub fn begin_step(&mut self) -> Result<(), UniverseError> {
+        let mut neighbors = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                neighbors.push(self.current_neighbors(x, y)?);
+            }
+        }
+        let counts = neighbors.iter().map(|cell| cell.iter().filter(|&&state| state != 0).count() as u8).collect();
+        self.pending = Some(PendingStep { neighbors, counts });
+        Ok(())
+    }
+
+    /// Live neighbors of every grid cell, row by row, between `begin_step`
+    /// and `finish_step`.
+    pub fn neighbor_counts(&self) -> Option<&[u8]> {
+        self.pending.as_ref().map(|pending| &pending.counts[..])
+    }
+
+    /// Second phase of a step taken in two, `rule` applied to the
+    /// neighbors read by `begin_step`: cells edited in between see their
+    /// neighbors as they were.
+    pub fn finish_step(&mut self, rule: &dyn Rule) -> Result<(), UniverseError> {
+        let pending = self.pending.take().ok_or(UniverseError::NoPendingStep)?;
+        let width = self.width;
+        self.apply_rule(rule, |_, x, y| Ok(pending.neighbors[y * width + x]))
+    }
+
+    fn current_neighbors(&self, x: usize, y: usize) -> Result<Neighbors, UniverseError> {
+        // Dead and absorbing edges see dead cells past the grid, which is
+        // what the guard ring holds.
+        if matches!(self.edges, EdgeBehavior::Dead | EdgeBehavior::Absorbing{..}) {
+            Ok(self.guarded_neighbor_states(self.index(x, y)?))
+        } else {
+            self.neighbor_states(x, y)
+        }
+    }
+
+    fn apply_rule<F>(&mut self, rule: &dyn Rule, neighbors_of: F) -> Result<(), UniverseError>
+    where F: Fn(&Universe, usize, usize) -> Result<Neighbors, UniverseError> {
+        if rule.states() != self.states {
+            return Err(UniverseError::RuleMismatch { rule_states: rule.states(), states: self.states });
+        }
+        let generation = self.next_generation()?;
+        // The cells before the last step are written over, so a step never
+        // holds more than two generations nor allocates once the second
+        // buffer exists. Its guard ring and padding are still dead.
+        let mut next = match self.previous.take() {
+            Some(buffer) => buffer,
+            None => BitsField::auto(bits_per_cell(self.states), self.guarded_len())?,
+        };
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = self.guarded_index(x, y);
+                let cell = self.cells.get(index)?;
+                if self.frozen.get::<bool>(index)? || self.edges.absorbs(x, y, self.width, self.height) {
+                    self.dirty_rows[y] |= cell != 0;
+                    next.set(index, 0u8)?;
+                    continue;
+                }
+                let state = rule.next_state(cell, &neighbors_of(self, x, y)?);
+                self.dirty_rows[y] |= state != cell;
+                next.set(index, state)?;
+            }
+        }
+        self.previous = Some(std::mem::replace(&mut self.cells, next));
+        self.generation = generation;
+        Ok(())
+    }
+
+    /// Run `generations` generations of `rule`.
+    ///
+    /// The dense grid has no faster way than stepping, jumps of a power of
+    /// two generations take as long as any other.
+    pub fn advance_generations(&mut self, rule: &dyn Rule, generations: u64) -> Result<(), UniverseError> {
+        for _ in 0..generations {
+            self.step(rule)?;
+        }
+        Ok(())
+    }
+
+    /// Copy a pattern into the universe, its top left corner at (x, y).
+    ///
+    /// Dead cells of the pattern are copied too, the whole rectangle is
+    /// overwritten.
+    pub fn stamp(&mut self, pattern: &Pattern, x: usize, y: usize) -> Result<(), UniverseError> {
+        self.stamp_with(pattern, x, y, ClipMode::Error)
+    }
+
+    /// Same as `stamp`, with the cells past the edges handled by `clip`.
+    pub fn stamp_with(&mut self, pattern: &Pattern, x: usize, y: usize, clip: ClipMode) -> Result<(), UniverseError> {
+        let fits = |start: usize, len: usize, limit: usize| start.checked_add(len).is_some_and(|end| end <= limit);
+        if clip == ClipMode::Error && !(fits(x, pattern.width(), self.width) && fits(y, pattern.height(), self.height)) {
+            return Err(UniverseError::PatternDoesNotFit);
+        }
+        if self.width == 0 || self.height == 0 {
+            return Ok(());
+        }
+        for dy in 0..pattern.height() {
+            for dx in 0..pattern.width() {
+                let (cx, cy) = match clip {
+                    ClipMode::Wrap => ((x % self.width + dx % self.width) % self.width, (y % self.height + dy % self.height) % self.height),
+                    _ => match (x.checked_add(dx), y.checked_add(dy)) {
+                        (Some(cx), Some(cy)) if cx < self.width && cy < self.height => (cx, cy),
+                        _ => continue,
+                    },
+                };
+                self.write_cell(cx, cy, pattern.get(dx, dy)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a `stamp` of the pattern at (x, y) before doing it, for
+    /// editors to refuse or warn.
+    pub fn can_place(&self, pattern: &Pattern, x: usize, y: usize) -> Result<PlacementReport, UniverseError> {
+        let mut report = PlacementReport::default();
+        for dy in 0..pattern.height() {
+            for dx in 0..pattern.width() {
+                let state = pattern.get(dx, dy)?;
+                let (cx, cy) = (x.saturating_add(dx), y.saturating_add(dy));
+                if cx >= self.width || cy >= self.height {
+                    report.out_of_bounds.push((dx, dy));
+                    continue;
+                }
+                let current = self.get_cell(cx, cy)?;
+                if current != 0 && current != state {
+                    report.overwritten.push((cx, cy));
+                }
+                if state != 0 && self.is_frozen(cx, cy)? {
+                    report.frozen.push((cx, cy));
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Scroll the grid `dx` cells right and `dy` cells down, cells leaving
+    /// an edge coming back on the other one, frozen cells with them.
+    ///
+    /// Rows move as blocks of words and each row is rotated in place.
+    pub fn scroll(&mut self, dx: i64, dy: i64) -> Result<(), UniverseError> {
+        if self.width == 0 || self.height == 0 {
+            return Ok(());
+        }
+        let dx = dx.rem_euclid(self.width as i64) as isize;
+        let rows = (dy.rem_euclid(self.height as i64) as usize * self.pitch) as isize;
+        let grid = self.pitch..self.pitch * (self.height + 1);
+        let starts: Vec<usize> = (0..self.height).map(|y| self.guarded_index(0, y)).collect();
+        let width = self.width;
+        for field in std::iter::once(&mut self.cells).chain(Some(&mut self.frozen)).chain(self.previous.as_mut()) {
+            field.rotate_range(grid.clone(), rows)?;
+            for &start in &starts {
+                field.rotate_range(start..start + width, dx)?;
+            }
+        }
+        self.dirty_rows.iter_mut().for_each(|dirty| *dirty = true);
+        Ok(())
+    }
+
+    /// Bring to life, in state 1, every dead cell at most `k` cells from a
+    /// live one in any of the eight directions: halos around patterns.
+    /// Frozen cells stay dead.
+    ///
+    /// Like `erode` and `outline`, live means any state but 0 and the cells
+    /// past the edges count as dead whatever the edge behavior. The live
+    /// cells are moved as one bit masks, a word at a time.
+    pub fn dilate(&mut self, k: usize) -> Result<(), UniverseError> {
+        let live = self.mask_of(&self.cells)?;
+        let mut born = self.morph(live.clone(), k, true)?;
+        born ^= &live;
+        let mut walls = self.mask_of(&self.frozen)?;
+        walls &= &born;
+        born ^= &walls;
+        self.write_mask(&born, 1)
+    }
+
+    /// Kill every live cell at most `k` cells (Chebyshev distance) from a
+    /// dead one.
+    pub fn erode(&mut self, k: usize) -> Result<(), UniverseError> {
+        let live = self.mask_of(&self.cells)?;
+        let mut dead = self.morph(live.clone(), k, false)?;
+        dead ^= &live;
+        self.write_mask(&dead, 0)
+    }
+
+    /// Only keep the live cells next to a dead one, the border of every
+    /// shape.
+    pub fn outline(&mut self) -> Result<(), UniverseError> {
+        let interior = self.morph(self.mask_of(&self.cells)?, 1, false)?;
+        self.write_mask(&interior, 0)
+    }
+
+    /// One bit per guarded cell, set where `field` is not 0.
+    fn mask_of(&self, field: &AutoBitsField) -> Result<BitsField<u64>, UniverseError> {
+        let mut mask = BitsField::new(1, self.guarded_len())?;
+        let mut from = 0;
+        while let Some(index) = field.find_next_not(from, 0u8) {
+            mask.set(index, true)?;
+            from = index + 1;
+        }
+        Ok(mask)
+    }
+
+    /// `mask` grown or shrunk by `k` cells, along the rows then along the
+    /// columns, the dead guard ring and padding shrinking it at the edges.
+    fn morph(&self, mut mask: BitsField<u64>, k: usize, grow: bool) -> Result<BitsField<u64>, UniverseError> {
+        // Past the size of the grid, nothing changes any more.
+        let k = k.min(self.width.max(self.height));
+        // Grown bits spilling into the padding would reach the next row.
+        let mut grid = BitsField::new(1, if grow { self.guarded_len() } else { 0 })?;
+        if grow {
+            let mut row = BitsField::new(1, self.width)?;
+            row.update(|_, _: bool| true);
+            for y in 0..self.height {
+                grid.splice(self.guarded_index(0, y), &row, self.width)?;
+            }
+        }
+        for distance in [1, self.pitch] {
+            for _ in 0..k {
+                let (mut before, mut after) = (mask.clone(), mask.clone());
+                before.shift_left(distance, false);
+                after.shift_right(distance, false);
+                if grow {
+                    mask |= &before;
+                    mask |= &after;
+                    mask &= &grid;
+                } else {
+                    mask &= &before;
+                    mask &= &after;
+                }
+            }
+        }
+        Ok(mask)
+    }
+
+    /// Set every grid cell of `mask` to `state`.
+    fn write_mask(&mut self, mask: &BitsField<u64>, state: u8) -> Result<(), UniverseError> {
+        let mut from = 0;
+        while let Some(index) = mask.find_next(from, true) {
+            self.write_cell(index % self.pitch - self.lead, index / self.pitch - 1, state)?;
+            from = index + 1;
+        }
+        Ok(())
+    }
+
+    /// Run a compound edit, like several stamps, all or nothing: when `edit`
+    /// fails the universe is put back as it was before, so a recorded
+    /// history or a peer never sees half of it.
+    ///
+    /// The universe is cloned first, keep transactions for edits.
+    pub fn transaction<R, E, F>(&mut self, edit: F) -> Result<R, E>
+    where F: FnOnce(&mut Universe) -> Result<R, E> {
+        let backup = self.clone();
+        let result = edit(self);
+        if result.is_err() {
+            *self = backup;
+        }
+        result
+    }
+
+    /// Same as `stamp`, with the top left corner of the pattern at a signed
+    /// point.
+    pub fn stamp_at(&mut self, pattern: &Pattern, point: Point) -> Result<(), UniverseError> {
+        let (x, y) = self.to_grid(point).ok_or(UniverseError::PatternDoesNotFit)?;
+        self.stamp(pattern, x, y)
+    }
+
+}
+
+/// Direct access to the cell memory, for renderers reading it from
+/// JavaScript.
+///
+/// Cells take `bitsPerCell` bits, packed from the lowest bit of each byte.
+/// Rows are padded, row `y` starts at `rowPtr(y)` and the next one
+/// `rowPitchBytes` later. Pointers are only valid until the next step.
+#[wasm_bindgen]
+impl Universe {
+
+    #[wasm_bindgen(js_name = bitsPerCell)]
+    pub fn bits_per_cell(&self) -> usize {
+        bits_per_cell(self.states)
+    }
+
+    #[wasm_bindgen(js_name = rowPitchBytes)]
+    pub fn row_pitch_bytes(&self) -> usize {
+        self.pitch * bits_per_cell(self.states) / 8
+    }
+
+    /// Address of the first cell of row `y`, null past the last row.
+    ///
+    /// Wide words are read as bytes, which matches the packing on little
+    /// endian targets like wasm.
+    #[wasm_bindgen(js_name = rowPtr)]
+    pub fn row_ptr(&self, y: usize) -> *const u8 {
+        if y >= self.height {
+            return std::ptr::null();
+        }
+        let byte = self.guarded_index(0, y) * bits_per_cell(self.states) / 8;
+        // The byte is inside the cells, the padding rows are past it.
+        unsafe { self.cells.as_bytes_ptr().add(byte) }
+    }
+
+}
+
+/// What is saved of a universe: its cells and settings, not the edits in
+/// progress (capture, symmetry) nor the dirty rows.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+#[serde(rename = "Universe")]
+struct SnapshotRef<'a> {
+    width: usize,
+    height: usize,
+    states: usize,
+    origin: Point,
+    edges: EdgeBehavior,
+    generation: u64,
+    generation_overflow: GenerationOverflow,
+    cells: &'a AutoBitsField,
+    frozen: &'a AutoBitsField,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Universe")]
+struct Snapshot {
+    width: usize,
+    height: usize,
+    states: usize,
+    origin: Point,
+    edges: EdgeBehavior,
+    generation: u64,
+    /// Missing from snapshots saved before it existed.
+    #[serde(default)]
+    generation_overflow: GenerationOverflow,
+    cells: AutoBitsField,
+    frozen: AutoBitsField,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Universe {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        SnapshotRef {
+            width: self.width,
+            height: self.height,
+            states: self.states,
+            origin: self.origin,
+            edges: self.edges,
+            generation: self.generation,
+            generation_overflow: self.generation_overflow,
+            cells: &self.cells,
+            frozen: &self.frozen,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Universe {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        use serde::de::Error;
+
+        let snapshot = Snapshot::deserialize(deserializer)?;
+        let mut universe = Universe::with_states(snapshot.width, snapshot.height, snapshot.states).map_err(D::Error::custom)?;
+        // The fields must have the layout of the universe, guard ring and
+        // padding included.
+        if universe.cells.layout() != snapshot.cells.layout() || universe.frozen.layout() != snapshot.frozen.layout() {
+            return Err(D::Error::custom("cells do not match the size of the universe"));
+        }
+        universe.origin = snapshot.origin;
+        universe.edges = snapshot.edges;
+        universe.generation = snapshot.generation;
+        universe.generation_overflow = snapshot.generation_overflow;
+        universe.cells = snapshot.cells;
+        universe.frozen = snapshot.frozen;
+        universe.check_storage().map_err(D::Error::custom)?;
+        Ok(universe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AutoBitsField, ClipMode, GenerationOverflow, InitPolicy, Pattern, Point, Rect, Size, Symmetry, Universe, UniverseError};
+    use crate::game_of_life::rules;
+    use crate::game_of_life::Xoshiro256;
+
+    #[test]
+    fn test_stamp_ascii_art() {
+        let mut universe = Universe::new(8, 8).unwrap();
+        let glider = Pattern::from_ascii_art(".O.\n..O\nOOO", "O");
+        universe.stamp(&glider, 2, 3).unwrap();
+
+        assert!(universe.is_alive(3, 3).unwrap());
+        assert!(!universe.is_alive(2, 3).unwrap());
+        assert!(universe.is_alive(4, 5).unwrap());
+        assert!(universe.stamp(&glider, 6, 0).is_err());
+    }
+
+    #[test]
+    fn test_centered_origin() {
+        let mut universe = Universe::new(9, 7).unwrap();
+        universe.center_origin();
+        assert_eq!(universe.to_grid(Point::ORIGIN), Some((4, 3)));
+        assert_eq!(universe.to_point(0, 0), Point::new(-4, -3));
+        assert_eq!(universe.to_grid(Point::new(-5, 0)), None);
+        assert_eq!(universe.signed_bounds().end(), Point::new(5, 4));
+
+        let glider = Pattern::from_ascii_art(".O.\n..O\nOOO", "O");
+        universe.stamp_at(&glider, Point::new(-1, -1)).unwrap();
+        assert!(universe.is_alive(4, 2).unwrap());
+        assert_eq!(universe.get_cell_at(Point::new(1, 1)).unwrap(), 1);
+        universe.set_cell_at(Point::new(-4, 3), 1).unwrap();
+        assert!(universe.is_alive(0, 6).unwrap());
+        assert!(universe.set_cell_at(Point::new(5, 0), 1).is_err());
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        assert_eq!(Universe::new(1 << 20, 1 << 20).err(), Some(UniverseError::TooLarge));
+        assert_eq!(Universe::with_states(usize::MAX, 2, 256).err(), Some(UniverseError::TooLarge));
+        assert_eq!(Universe::with_states(4, 4, 300).err(), Some(UniverseError::InvalidStates(300)));
+        assert!(Universe::memory_needed(1 << 20, 1 << 20, 2).unwrap() > super::memory_limit());
+        assert!(Universe::memory_needed(64, 64, 2).unwrap() < 4096);
+    }
+
+    #[test]
+    fn test_initialize() {
+        let mut first = Universe::new(16, 16).unwrap();
+        let mut second = Universe::new(16, 16).unwrap();
+        let policy = InitPolicy::Gaussian{alive_probability: 0.9, sigma: 4.0};
+        first.initialize(&policy, &mut Xoshiro256::new(3)).unwrap();
+        second.initialize(&policy, &mut Xoshiro256::new(3)).unwrap();
+        let live: Vec<bool> = (0..256).map(|i| first.is_alive(i % 16, i / 16).unwrap()).collect();
+        assert!(live.iter().enumerate().all(|(i, &alive)| alive == second.is_alive(i % 16, i / 16).unwrap()));
+        // Corners are more than three deviations away from the center.
+        assert!(!live[0] && !live[255]);
+
+        let mut custom = Universe::with_states(2, 1, 3).unwrap();
+        custom.initialize(&InitPolicy::Custom{states: vec![2, 1]}, &mut Xoshiro256::new(0)).unwrap();
+        assert_eq!(custom.get_cell(0, 0).unwrap(), 2);
+        assert!(custom.initialize(&InitPolicy::Custom{states: vec![1]}, &mut Xoshiro256::new(0)).is_err());
+    }
+
+    #[test]
+    fn test_step_with_rule() {
+        let life = rules::parse("B3/S23").unwrap();
+        let mut universe = Universe::new(5, 5).unwrap();
+        universe.stamp(&Pattern::from_ascii_art("OOO", "O"), 1, 2).unwrap();
+        universe.step(life.as_ref()).unwrap();
+        assert!((1..4).all(|y| universe.is_alive(2, y).unwrap()));
+        assert!(!universe.is_alive(1, 2).unwrap());
+
+        // A wall next to the blinker stops it from growing into it.
+        universe.set_frozen(1, 2, true).unwrap();
+        universe.step(life.as_ref()).unwrap();
+        assert!(!universe.is_alive(1, 2).unwrap());
+        assert!(universe.is_alive(3, 2).unwrap());
+        assert!(universe.step(rules::parse("B2/S/C3").unwrap().as_ref()).is_err());
+        assert_eq!(universe.generation(), 2);
+
+        // Without the wall, the blinker has a period of two.
+        universe.clear_frozen().unwrap();
+        universe.stamp(&Pattern::from_ascii_art("OOO", "O"), 1, 2).unwrap();
+        universe.advance_generations(life.as_ref(), 2u64.pow(3)).unwrap();
+        assert_eq!(universe.generation(), 10);
+        assert!(universe.is_alive(1, 2).unwrap() && !universe.is_alive(2, 1).unwrap());
+    }
+
+    #[test]
+    fn test_tick() {
+        let alive = |universe: &Universe| universe.live_cells().collect::<Vec<_>>();
+
+        let mut blinker = Universe::new(5, 5).unwrap();
+        blinker.stamp(&Pattern::from_ascii_art("OOO", "O"), 1, 2).unwrap();
+        for generation in 1..=4 {
+            blinker.tick().unwrap();
+            let expected = if generation % 2 == 1 { vec![(2, 1), (2, 2), (2, 3)] } else { vec![(1, 2), (2, 2), (3, 2)] };
+            assert_eq!(alive(&blinker), expected);
+        }
+
+        let mut block = Universe::new(4, 4).unwrap();
+        block.stamp(&Pattern::from_ascii_art("OO\nOO", "O"), 1, 1).unwrap();
+        let start = alive(&block);
+        for _ in 0..3 {
+            block.tick().unwrap();
+            assert_eq!(alive(&block), start);
+        }
+
+        // A glider moves one cell down and right every four generations.
+        let mut glider = Universe::new(8, 8).unwrap();
+        glider.stamp(&Pattern::from_ascii_art(".O.\n..O\nOOO", "O"), 0, 0).unwrap();
+        let start = alive(&glider);
+        for _ in 0..8 {
+            glider.tick().unwrap();
+        }
+        assert_eq!(alive(&glider), start.iter().map(|&(x, y)| (x + 2, y + 2)).collect::<Vec<_>>());
+        assert_eq!(glider.generation(), 8);
+
+        // The two buffers take turns.
+        let buffer = glider.row_ptr(0);
+        glider.tick().unwrap();
+        assert_ne!(glider.row_ptr(0), buffer);
+        glider.tick().unwrap();
+        assert_eq!(glider.row_ptr(0), buffer);
+        assert!(Universe::with_states(4, 4, 3).unwrap().tick().is_err());
+    }
+
+    #[test]
+    fn test_capture() {
+        let mut universe = Universe::with_states(8, 8, 3).unwrap();
+        universe.set_cell(0, 0, 1).unwrap();
+        assert_eq!(universe.stop_capture(), None);
+
+        universe.start_capture();
+        universe.set_cell(2, 5, 1).unwrap();
+        universe.set_cell(4, 3, 1).unwrap();
+        universe.set_cell(4, 3, 2).unwrap();
+        let (bounds, pattern) = universe.stop_capture().unwrap();
+        assert_eq!(bounds, Rect::new(Point::new(2, 3), Size::new(3, 3)));
+        assert_eq!(pattern.cells(), &[0, 0, 2, 0, 0, 0, 1, 0, 0]);
+        assert!(!universe.is_capturing());
+    }
+
+    #[test]
+    fn test_guard_ring() {
+        let mut universe = Universe::new(9, 7).unwrap();
+        universe.initialize(&InitPolicy::Random{alive_probability: 0.5}, &mut Xoshiro256::new(3)).unwrap();
+        for y in 0..7 {
+            for x in 0..9 {
+                let guarded = universe.guarded_neighbor_states(universe.guarded_index(x, y));
+                assert_eq!(guarded, universe.neighbor_states(x, y).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_generation_overflow() {
+        let life = rules::parse("B3/S23").unwrap();
+        let mut universe = Universe::new(3, 3).unwrap();
+        universe.set_cell(1, 1, 1).unwrap();
+        universe.set_generation(u64::MAX);
+        assert_eq!(universe.step(life.as_ref()), Err(UniverseError::GenerationOverflow));
+        assert_eq!(universe.population(), 1);
+
+        universe.set_generation_overflow(GenerationOverflow::Saturate);
+        universe.step(life.as_ref()).unwrap();
+        assert_eq!(universe.generation(), u64::MAX);
+
+        universe.set_generation_overflow(GenerationOverflow::Wrap);
+        universe.advance_generations(life.as_ref(), 3).unwrap();
+        assert_eq!(universe.generation(), 2);
+        assert_eq!(universe.generations_since(u64::MAX - 1), 4);
+    }
+
+    #[test]
+    fn test_step_in_phases() {
+        let life = rules::parse("B3/S23").unwrap();
+        let mut universe = Universe::new(5, 5).unwrap();
+        assert_eq!(universe.finish_step(life.as_ref()), Err(UniverseError::NoPendingStep));
+        universe.stamp(&Pattern::from_ascii_art("OOO", "O"), 1, 2).unwrap();
+        let mut stepped = universe.clone();
+        stepped.step(life.as_ref()).unwrap();
+
+        universe.begin_step().unwrap();
+        let counts = universe.neighbor_counts().unwrap();
+        assert_eq!(&counts[5..15], &[1, 2, 3, 2, 1, 1, 1, 2, 1, 1]);
+        universe.finish_step(life.as_ref()).unwrap();
+        assert_eq!(universe.neighbor_counts(), None);
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), stepped.live_cells().collect::<Vec<_>>());
+
+        // Edits between the phases do not change the neighbors read.
+        universe.begin_step().unwrap();
+        universe.set_cell(0, 0, 1).unwrap();
+        universe.finish_step(life.as_ref()).unwrap();
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), vec![(1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn test_row_pointers() {
+        let mut universe = Universe::with_states(10, 3, 3).unwrap();
+        universe.set_cell(0, 1, 2).unwrap();
+        universe.set_cell(5, 1, 1).unwrap();
+        // Two bits per cell, lead byte, ten cells and one dead cell take 4 bytes.
+        assert_eq!((universe.bits_per_cell(), universe.row_pitch_bytes()), (2, 4));
+        let row = unsafe { std::slice::from_raw_parts(universe.row_ptr(1), 3) };
+        assert_eq!(row, &[0b10, 0b01 << 2, 0]);
+        let next = universe.row_ptr(2) as usize - universe.row_ptr(1) as usize;
+        assert_eq!(next, universe.row_pitch_bytes());
+        assert!(universe.row_ptr(3).is_null());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut universe = Universe::with_states(6, 4, 3).unwrap();
+        universe.set_cell(5, 3, 2).unwrap();
+        universe.set_frozen(0, 1, true).unwrap();
+        universe.set_edges(crate::game_of_life::EdgeBehavior::Wrap);
+        universe.set_generation(12);
+        universe.set_generation_overflow(GenerationOverflow::Wrap);
+
+        let json = serde_json::to_string(&universe).unwrap();
+        let copy: Universe = serde_json::from_str(&json).unwrap();
+        assert_eq!((copy.width(), copy.height(), copy.states(), copy.generation()), (6, 4, 3, 12));
+        assert_eq!(copy.edges(), universe.edges());
+        assert_eq!(copy.generation_overflow(), GenerationOverflow::Wrap);
+        assert_eq!(copy.get_cell(5, 3).unwrap(), 2);
+        assert!(copy.is_frozen(0, 1).unwrap());
+
+        let resized = json.replacen("\"width\":6", "\"width\":60", 1);
+        assert!(serde_json::from_str::<Universe>(&resized).is_err());
+        // Tampered storage: a guard cell, a state past 3, a frozen padding
+        // cell.
+        let tampered: [fn(&mut Universe); 3] = [
+            |universe| universe.cells.set(0, 1u8).unwrap(),
+            |universe| universe.cells.set(universe.guarded_index(1, 1), 3u8).unwrap(),
+            |universe| universe.frozen.set(universe.guarded_index(5, 0) + 1, true).unwrap(),
+        ];
+        for tamper in tampered.iter() {
+            let mut copy = universe.clone();
+            tamper(&mut copy);
+            assert!(serde_json::from_str::<Universe>(&serde_json::to_string(&copy).unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_live_cells() {
+        let mut universe = Universe::with_states(70, 4, 3).unwrap();
+        assert_eq!(universe.live_cells().count(), 0);
+        universe.set_cell(69, 0, 1).unwrap();
+        universe.set_cell(0, 3, 2).unwrap();
+        universe.set_cell(5, 1, 1).unwrap();
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), vec![(69, 0), (5, 1), (0, 3)]);
+    }
+
+    #[test]
+    fn test_state_histogram() {
+        let mut universe = Universe::with_states(20, 10, 4).unwrap();
+        universe.set_cell(0, 0, 3).unwrap();
+        universe.set_cell(19, 9, 3).unwrap();
+        universe.set_cell(5, 5, 1).unwrap();
+        assert_eq!(universe.state_histogram(), vec![197, 1, 0, 2]);
+        assert_eq!(Universe::with_states(3, 3, 256).unwrap().state_histogram()[..2], [9, 0]);
+    }
+
+    #[test]
+    fn test_stamp_clip_modes() {
+        let mut universe = Universe::new(5, 4).unwrap();
+        let line = Pattern::from_ascii_art("OOO", "O");
+        assert_eq!(universe.stamp(&line, 3, 0), Err(UniverseError::PatternDoesNotFit));
+        assert_eq!(universe.stamp(&line, usize::MAX, 0), Err(UniverseError::PatternDoesNotFit));
+        universe.stamp_with(&line, 3, 0, ClipMode::Clip).unwrap();
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), vec![(3, 0), (4, 0)]);
+        universe.stamp_with(&line, 4, 7, ClipMode::Wrap).unwrap();
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), vec![(3, 0), (4, 0), (0, 3), (1, 3), (4, 3)]);
+        universe.stamp_with(&line, usize::MAX, 0, ClipMode::Clip).unwrap();
+        assert_eq!(universe.population(), 5);
+    }
+
+    #[test]
+    fn test_toggle_cell() {
+        let mut universe = Universe::new(4, 4).unwrap();
+        universe.clear_dirty_rows();
+        universe.toggle_cell(1, 2).unwrap();
+        assert_eq!(universe.get_cell(1, 2).unwrap(), 1);
+        assert!(universe.dirty_rows()[2]);
+        universe.toggle_cell(1, 2).unwrap();
+        assert_eq!(universe.population(), 0);
+        assert_eq!(universe.toggle_cell(4, 0), Err(UniverseError::OutOfBounds));
+
+        let mut generations = Universe::with_states(4, 4, 5).unwrap();
+        generations.set_cell(0, 0, 3).unwrap();
+        generations.toggle_cell(0, 0).unwrap();
+        generations.toggle_cell(1, 0).unwrap();
+        assert_eq!((generations.get_cell(0, 0).unwrap(), generations.get_cell(1, 0).unwrap()), (0, 1));
+
+        // A click in a symmetric drawing edits the mirrored cells too.
+        universe.set_symmetry(Symmetry::Horizontal, Point::new(1, 1));
+        universe.toggle_cell(0, 1).unwrap();
+        assert!(universe.is_alive(0, 1).unwrap() && universe.is_alive(2, 1).unwrap());
+        universe.toggle_cell(2, 1).unwrap();
+        assert_eq!(universe.population(), 0);
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut universe = Universe::with_states(40, 3, 3).unwrap();
+        universe.set_cell(39, 0, 1).unwrap();
+        universe.set_cell(2, 2, 2).unwrap();
+        universe.set_frozen(5, 1, true).unwrap();
+        universe.clear_dirty_rows();
+        universe.scroll(3, -1).unwrap();
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), vec![(5, 1), (2, 2)]);
+        assert_eq!(universe.get_cell(5, 1).unwrap(), 2);
+        assert_eq!(universe.get_cell(2, 2).unwrap(), 1);
+        assert!(universe.is_frozen(8, 0).unwrap());
+        assert!(universe.dirty_rows().iter().all(|&dirty| dirty));
+        universe.scroll(-3 + 40, 1 - 3 * 100).unwrap();
+        assert_eq!(universe.live_cells().collect::<Vec<_>>(), vec![(39, 0), (2, 2)]);
+    }
+
+    #[test]
+    fn test_morphology() {
+        let alive = |universe: &Universe| universe.live_cells().collect::<Vec<_>>();
+        let mut universe = Universe::with_states(70, 6, 3).unwrap();
+        universe.set_cell(64, 2, 2).unwrap();
+        universe.set_cell(0, 0, 1).unwrap();
+        universe.set_frozen(65, 3, true).unwrap();
+        universe.dilate(1).unwrap();
+        assert_eq!(universe.population(), 8 + 4);
+        assert_eq!(universe.get_cell(64, 2).unwrap(), 2);
+        assert_eq!(universe.get_cell(63, 1).unwrap(), 1);
+        assert!(!universe.is_alive(65, 3).unwrap() && !universe.is_alive(69, 1).unwrap());
+
+        // The block in the corner touches the dead cells past the edges.
+        universe.clear_frozen().unwrap();
+        universe.set_cell(65, 3, 1).unwrap();
+        universe.erode(1).unwrap();
+        assert_eq!(alive(&universe), vec![(64, 2)]);
+        universe.dilate(2).unwrap();
+        assert_eq!(universe.population(), 25);
+        universe.outline().unwrap();
+        assert_eq!(universe.population(), 16);
+        assert!(!universe.is_alive(64, 2).unwrap() && universe.is_alive(62, 0).unwrap());
+        universe.erode(3).unwrap();
+        assert_eq!(universe.population(), 0);
+
+        let mut block = Universe::new(5, 5).unwrap();
+        block.stamp(&Pattern::from_ascii_art("OOO\nOOO\nOOO", "O"), 1, 1).unwrap();
+        block.outline().unwrap();
+        assert_eq!(block.population(), 8);
+        block.dilate(100).unwrap();
+        assert_eq!(block.population(), 25);
+        block.erode(2).unwrap();
+        assert_eq!(alive(&block), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut universe = Universe::new(5, 5).unwrap();
+        let block = Pattern::from_ascii_art("OO\nOO", "O");
+        let result = universe.transaction(|tx| {
+            tx.stamp(&block, 0, 0)?;
+            tx.set_frozen(4, 0, true)?;
+            tx.stamp(&block, 4, 4)
+        });
+        assert_eq!(result, Err(UniverseError::PatternDoesNotFit));
+        assert_eq!(universe.population(), 0);
+        assert!(!universe.is_frozen(4, 0).unwrap());
+
+        universe.transaction(|tx| tx.stamp(&block, 0, 0).and_then(|_| tx.stamp(&block, 3, 3))).unwrap();
+        assert_eq!(universe.population(), 8);
+    }
+
+    #[test]
+    fn test_can_place() {
+        let mut universe = Universe::new(5, 5).unwrap();
+        let block = Pattern::from_ascii_art("OO\nO.", "O");
+        assert!(universe.can_place(&block, 0, 0).unwrap().is_clear());
+
+        universe.set_cell(1, 1, 1).unwrap();
+        universe.set_cell(4, 4, 1).unwrap();
+        universe.set_frozen(3, 3, true).unwrap();
+        let report = universe.can_place(&block, 0, 0).unwrap();
+        assert_eq!(report.overwritten, vec![(1, 1)]);
+        let report = universe.can_place(&block, 3, 3).unwrap();
+        assert_eq!(report.overwritten, vec![(4, 4)]);
+        assert_eq!(report.frozen, vec![(3, 3)]);
+        assert!(report.fits() && !report.is_clear());
+        // Out of bounds dead cells count too, stamp would refuse them.
+        assert_eq!(universe.can_place(&block, 4, 4).unwrap().out_of_bounds, vec![(1, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn test_backing_word_selection() {
+        // Even the smallest grid fills a word with its guard ring.
+        let tiny = Universe::new(1, 1).unwrap();
+        assert!(matches!(tiny.cells, AutoBitsField::U32(_)));
+        let multi_state = Universe::with_states(64, 64, 16).unwrap();
+        assert!(matches!(multi_state.cells, AutoBitsField::U32(_)));
+        let bytes = Universe::with_states(64, 64, 256).unwrap();
+        assert!(matches!(bytes.cells, AutoBitsField::U8(_)));
+
+        let mut wide = Universe::with_states(30, 20, 3).unwrap();
+        assert_eq!(wide.cells.layout().word_bits, if cfg!(target_pointer_width = "64") { 64 } else { 32 });
+        wide.set_cell(29, 19, 2).unwrap();
+        wide.set_frozen(3, 7, true).unwrap();
+        assert_eq!(wide.get_cell(29, 19).unwrap(), 2);
+        assert!(wide.is_frozen(3, 7).unwrap());
+    }
+
 }
\ No newline at end of file
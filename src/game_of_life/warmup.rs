@@ -0,0 +1,113 @@
+use super::{Rule, Universe, UniverseError};
+
+/// When to stop fast-forwarding a universe.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarmupStop {
+    /// After exactly this many generations.
+    Generations(u64),
+    /// Once less than `threshold` of the cells changed in a generation, or
+    /// after `max_generations` if the universe never settles.
+    Activity{threshold: f64, max_generations: u64},
+}
+
+/// Fast-forwards a freshly randomized universe past its first chaotic
+/// generations, a few generations at a time.
+///
+/// Call `advance` once a frame with a small budget, so the first frame is
+/// drawn quickly and the demo starts once the warmup is done.
+pub struct Warmup {
+    stop: WarmupStop,
+    generations: u64,
+    done: bool,
+}
+
+impl Warmup {
+
+    pub fn new(stop: WarmupStop) -> Self {
+        Warmup { stop, generations: 0, done: false }
+    }
+
+    /// Generations run so far.
+    pub fn generations(&self) -> u64 {
+        self.generations
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Run at most `budget` generations of `rule`, return whether the
+    /// warmup is done.
+    pub fn advance(&mut self, universe: &mut Universe, rule: &dyn Rule, budget: u64) -> Result<bool, UniverseError> {
+        for _ in 0..budget {
+            if self.done {
+                break;
+            }
+            match self.stop {
+                WarmupStop::Generations(generations) => {
+                    if self.generations < generations {
+                        universe.step(rule)?;
+                        self.generations += 1;
+                    }
+                    self.done = self.generations >= generations;
+                }
+                WarmupStop::Activity{threshold, max_generations} => {
+                    if self.generations >= max_generations {
+                        self.done = true;
+                        break;
+                    }
+                    let before = universe.clone();
+                    universe.step(rule)?;
+                    self.generations += 1;
+                    self.done = activity(&before, universe)? < threshold || self.generations >= max_generations;
+                }
+            }
+        }
+        Ok(self.done)
+    }
+
+    /// Run the whole warmup at once.
+    pub fn run(&mut self, universe: &mut Universe, rule: &dyn Rule) -> Result<(), UniverseError> {
+        self.advance(universe, rule, u64::MAX)?;
+        Ok(())
+    }
+
+}
+
+/// Fraction of the cells that changed between two universes of the same size.
+fn activity(before: &Universe, after: &Universe) -> Result<f64, UniverseError> {
+    let mut changed = 0;
+    for y in 0..after.height() {
+        for x in 0..after.width() {
+            if before.get_cell(x, y)? != after.get_cell(x, y)? {
+                changed += 1;
+            }
+        }
+    }
+    Ok(changed as f64 / (after.width() * after.height()).max(1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Warmup, WarmupStop};
+    use crate::game_of_life::{rules, InitPolicy, Universe, Xoshiro256};
+
+    #[test]
+    fn test_warmup() {
+        let life = rules::parse("B3/S23").unwrap();
+        let mut universe = Universe::new(24, 24).unwrap();
+        universe.initialize(&InitPolicy::Random{alive_probability: 0.4}, &mut Xoshiro256::new(7)).unwrap();
+
+        let mut warmup = Warmup::new(WarmupStop::Generations(10));
+        assert!(!warmup.advance(&mut universe, life.as_ref(), 4).unwrap());
+        assert_eq!(universe.generation(), 4);
+        assert!(warmup.advance(&mut universe, life.as_ref(), 100).unwrap());
+        assert_eq!(universe.generation(), 10);
+
+        let mut warmup = Warmup::new(WarmupStop::Activity{threshold: 0.02, max_generations: 200});
+        warmup.run(&mut universe, life.as_ref()).unwrap();
+        assert!(warmup.is_done());
+        assert!(warmup.generations() > 0 && warmup.generations() <= 200);
+    }
+
+}
@@ -2,7 +2,7 @@ extern crate cfg_if;
 extern crate wasm_bindgen;
 
 mod utils;
-mod game_of_life;
+pub mod game_of_life;
 
 use cfg_if::cfg_if;
 use wasm_bindgen::prelude::*;
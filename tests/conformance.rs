@@ -0,0 +1,99 @@
+//! Compatibility tests against Golly.
+//!
+//! The patterns in `tests/conformance` are RLE files as Golly saves them.
+//! Each one must be written back byte for byte, and must evolve as the
+//! pattern is known to on an unbounded plane. Universes are large enough
+//! that nothing ever touches their edges.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+use wasm_game_of_life::game_of_life::prelude::*;
+use wasm_game_of_life::game_of_life::Point;
+
+const MARGIN: usize = 24;
+
+enum Evolution {
+    /// Back to the same cells after `period` generations, moved by `shift`.
+    Period { period: u64, shift: Point },
+    /// Population of the first generations.
+    Populations(&'static [usize]),
+    /// No cell left at generation `generation`, and some at the previous one.
+    DiesAt { generation: u64 },
+}
+
+const FIXTURES: &[(&str, Evolution)] = &[
+    ("glider", Evolution::Period { period: 4, shift: Point::new(1, 1) }),
+    ("lwss", Evolution::Period { period: 4, shift: Point::new(-2, 0) }),
+    ("beacon", Evolution::Populations(&[6, 8, 6, 8])),
+    ("pulsar", Evolution::Populations(&[48, 56, 72, 48])),
+    ("pentadecathlon", Evolution::Period { period: 15, shift: Point::ORIGIN }),
+    ("diehard", Evolution::DiesAt { generation: 130 }),
+];
+
+fn fixture(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/conformance").join(format!("{}.rle", name));
+    fs::read_to_string(&path).unwrap_or_else(|_| panic!("missing {}", path.display()))
+}
+
+fn live_cells(universe: &Universe) -> BTreeSet<Point> {
+    universe.bounds().points().filter(|&point| universe.get_cell_at(point).unwrap() != 0).collect()
+}
+
+/// Run the pattern for `generations`, with the live cells of every generation.
+fn run(pattern: &Pattern, generations: u64) -> Vec<BTreeSet<Point>> {
+    let rule = pattern.rule_hint().expect("fixture without a rule").unwrap();
+    let mut universe = Universe::new(pattern.width() + 2 * MARGIN, pattern.height() + 2 * MARGIN).unwrap();
+    universe.stamp(pattern, MARGIN, MARGIN).unwrap();
+
+    let end = universe.bounds().end();
+    let mut history = vec![live_cells(&universe)];
+    for _ in 0..generations {
+        universe.step(rule.as_ref()).unwrap();
+        let cells = live_cells(&universe);
+        assert!(
+            cells.iter().all(|point| point.x > 0 && point.y > 0 && point.x < end.x - 1 && point.y < end.y - 1),
+            "pattern reached the edge of the universe"
+        );
+        history.push(cells);
+    }
+    history
+}
+
+#[test]
+fn test_rle_round_trip() {
+    for (name, _) in FIXTURES {
+        let text = fixture(name);
+        let pattern = Pattern::from_rle(&text).unwrap();
+        assert_eq!(pattern.to_rle().trim_end(), text.trim_end(), "{}: not written as Golly does", name);
+        assert_eq!(Pattern::from_rle(&pattern.to_rle()).unwrap(), pattern, "{}: changed by a round trip", name);
+    }
+}
+
+#[test]
+fn test_evolutions() {
+    for (name, evolution) in FIXTURES {
+        let pattern = Pattern::from_rle(&fixture(name)).unwrap();
+        match *evolution {
+            Evolution::Period { period, shift } => {
+                let history = run(&pattern, period);
+                let moved: BTreeSet<Point> = history[0].iter().map(|&point| point + shift).collect();
+                assert_eq!(history[period as usize], moved, "{}: wrong period or speed", name);
+                for generation in 1..period as usize {
+                    assert_ne!(history[generation], history[0], "{}: period shorter than {}", name, period);
+                }
+            }
+            Evolution::Populations(populations) => {
+                let history = run(&pattern, populations.len() as u64 - 1);
+                let actual: Vec<usize> = history.iter().map(BTreeSet::len).collect();
+                assert_eq!(actual, populations, "{}: wrong populations", name);
+            }
+            Evolution::DiesAt { generation } => {
+                let history = run(&pattern, generation);
+                assert!(history[generation as usize].is_empty(), "{}: still alive at {}", name, generation);
+                assert!(!history[generation as usize - 1].is_empty(), "{}: died before {}", name, generation);
+            }
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! `#[derive(BitFieldCompatible)]` on state enums.
+
+#![cfg(feature = "derive")]
+
+use wasm_game_of_life::game_of_life::{BitFieldCompatible, BitsField};
+
+#[derive(BitFieldCompatible, Clone, Copy, Debug, PartialEq)]
+#[bitfield(bits = 2)]
+enum Cell {
+    Empty,
+    Wire,
+    Head = 3,
+    Tail = 2,
+}
+
+#[test]
+fn test_derived_states() {
+    let mut field = BitsField::<u8>::new(2, 6).unwrap();
+    field.set(1, Cell::Wire).unwrap();
+    field.set(2, Cell::Head).unwrap();
+    field.set(3, Cell::Tail).unwrap();
+    assert_eq!(field.get::<u8>(2).unwrap(), 3);
+    let cells: Vec<Cell> = field.iter().take(4).collect();
+    assert_eq!(cells, vec![Cell::Empty, Cell::Wire, Cell::Head, Cell::Tail]);
+    assert_eq!(<Cell as BitFieldCompatible<u64>>::to_type(&Cell::Tail), 2);
+}
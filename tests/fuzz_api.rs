@@ -0,0 +1,82 @@
+//! Random calls to what the wasm API exposes, which must return errors on
+//! bad input and never panic: a panic aborts the whole wasm instance.
+//!
+//! The inputs are drawn from a seeded generator, so a failure replays with
+//! the seed printed by the assertion.
+
+use wasm_game_of_life::game_of_life::{CatalogQuery, InitPolicy, Pattern, PatternCatalog, Rng, Universe, Xoshiro256};
+
+const ROUNDS: u64 = 2000;
+
+/// Sizes and coordinates, small ones mixed with absurd ones.
+fn dimension(rng: &mut Xoshiro256) -> usize {
+    match rng.below(6) {
+        0 => 0,
+        1 => 1 << 40,
+        2 => usize::MAX,
+        3 => usize::MAX / 2 + 1,
+        _ => rng.below(40),
+    }
+}
+
+fn probability(rng: &mut Xoshiro256) -> f64 {
+    [f64::NAN, f64::INFINITY, -1.0, 2.0, 0.0, 1.0, rng.next_f64()][rng.below(7)]
+}
+
+/// Random bytes from the RLE alphabet, or a valid pattern mangled.
+fn rle(rng: &mut Xoshiro256) -> String {
+    const ALPHABET: &[u8] = b"0123456789bo.$!pqxyABX #NCO=,\nrule";
+    if rng.chance(0.5) {
+        let length = rng.below(64);
+        let header = format!("x = {}, y = {}\n", dimension(rng), dimension(rng));
+        header + &(0..length).map(|_| ALPHABET[rng.below(ALPHABET.len())] as char).collect::<String>()
+    } else {
+        let mut text = b"#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!".to_vec();
+        let at = rng.below(text.len());
+        text[at] = ALPHABET[rng.below(ALPHABET.len())];
+        String::from_utf8(text).unwrap()
+    }
+}
+
+#[test]
+fn test_universe_api() {
+    for seed in 0..ROUNDS {
+        let mut rng = Xoshiro256::new(seed);
+        let states = rng.below(300);
+        let (width, height) = (dimension(&mut rng), dimension(&mut rng));
+        let mut universe = match Universe::with_states(width, height, states) {
+            Ok(universe) => universe,
+            Err(_) => continue,
+        };
+        let _ = universe.row_ptr(dimension(&mut rng));
+        let _ = universe.row_pitch_bytes();
+        let (x, y) = (dimension(&mut rng), dimension(&mut rng));
+        let _ = universe.set_cell(x, y, rng.below(300) as u8);
+        let _ = universe.get_cell(x, y);
+        let policy = match rng.below(3) {
+            0 => InitPolicy::Random { alive_probability: probability(&mut rng) },
+            1 => InitPolicy::Gaussian { alive_probability: probability(&mut rng), sigma: probability(&mut rng) * 10.0 },
+            _ => InitPolicy::Custom { states: vec![rng.below(4) as u8; rng.below(width * height + 2)] },
+        };
+        let result = universe.initialize(&policy, &mut rng);
+        if let InitPolicy::Random { alive_probability } = policy {
+            assert!(result.is_ok() || !(0.0..=1.0).contains(&alive_probability), "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn test_catalog_api() {
+    let mut catalog = PatternCatalog::new();
+    for seed in 0..ROUNDS {
+        let mut rng = Xoshiro256::new(seed);
+        // What `addRle` does, without the JavaScript error value.
+        if let Ok(pattern) = Pattern::from_rle(&rle(&mut rng)) {
+            assert!(pattern.width().checked_mul(pattern.height()).is_some(), "seed {}", seed);
+            catalog.add(pattern, Some(rng.below(10)), vec!["fuzz".to_string()]);
+        }
+        let text: String = (0..rng.below(8)).map(|_| char::from_u32(rng.below(0x11000) as u32).unwrap_or('?')).collect();
+        let _ = catalog.search_json(&text, &text);
+        let _ = catalog.search(&CatalogQuery { text, ..CatalogQuery::default() });
+    }
+}
@@ -0,0 +1,176 @@
+//! Golden tests for the renderers.
+//!
+//! Each case renders a known pattern and compares it with the output stored
+//! in `tests/golden`: a PAM file for images, RGBA and PNG alike, and a text
+//! file for `TextRenderer`. Run with `UPDATE_GOLDEN=1` to write the current
+//! output as the new golden after an intended visual change.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use wasm_game_of_life::game_of_life::rules::Generations;
+use wasm_game_of_life::game_of_life::{Color, FontSize, Palette, Pattern, Renderer, TextRenderer, Universe};
+
+/// Largest difference allowed on a channel, so that rounding changes in the
+/// palette gradients do not break the tests.
+const TOLERANCE: u8 = 2;
+
+fn golden_path(name: &str, extension: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.{}", name, extension))
+}
+
+fn update_golden() -> bool {
+    std::env::var_os("UPDATE_GOLDEN").is_some()
+}
+
+fn read_golden(path: &Path) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|_| panic!("missing {}, run with UPDATE_GOLDEN=1 to create it", path.display()))
+}
+
+fn write_pam(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut file = format!(
+        "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+        width, height
+    )
+    .into_bytes();
+    file.extend_from_slice(rgba);
+    file
+}
+
+fn read_pam(file: &[u8]) -> (usize, usize, Vec<u8>) {
+    let end = file.windows(7).position(|window| window == b"ENDHDR\n").expect("PAM file without ENDHDR") + 7;
+    let header = std::str::from_utf8(&file[..end]).expect("PAM header is not text");
+    let field = |name: &str| -> usize {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or_else(|| panic!("PAM header without {}", name))
+    };
+    assert_eq!(field("DEPTH"), 4, "golden images must be RGBA");
+    (field("WIDTH"), field("HEIGHT"), file[end..].to_vec())
+}
+
+fn assert_golden(name: &str, width: usize, height: usize, rgba: &[u8]) {
+    let path = golden_path(name, "pam");
+    if update_golden() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, write_pam(width, height, rgba)).unwrap();
+        return;
+    }
+
+    let (golden_width, golden_height, golden) = read_pam(&read_golden(&path));
+    assert_eq!((width, height), (golden_width, golden_height), "{}: size changed", name);
+    assert_eq!(rgba.len(), golden.len(), "{}: wrong buffer length", name);
+    for (i, (&actual, &expected)) in rgba.iter().zip(golden.iter()).enumerate() {
+        let pixel = i / 4;
+        assert!(
+            actual.max(expected) - actual.min(expected) <= TOLERANCE,
+            "{}: pixel ({}, {}) channel {} is {} instead of {}",
+            name,
+            pixel % width,
+            pixel / width,
+            i % 4,
+            actual,
+            expected
+        );
+    }
+}
+
+/// Text has no tolerance, a single changed symbol is a visual change.
+fn assert_golden_text(name: &str, text: &str) {
+    let path = golden_path(name, "txt");
+    if update_golden() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, text).unwrap();
+        return;
+    }
+
+    let golden = String::from_utf8(read_golden(&path)).expect("golden text is not UTF-8");
+    assert_eq!(text, golden, "{}: text changed", name);
+}
+
+fn glider() -> Universe {
+    let glider = Pattern::from_ascii_art(
+        "
+        .#.
+        ..#
+        ###
+        ",
+        "#",
+    );
+    let mut universe = Universe::new(6, 5).unwrap();
+    universe.stamp(&glider, 1, 1).unwrap();
+    universe
+}
+
+#[test]
+fn test_rgba_default_palette() {
+    let universe = glider();
+    let rgba = Renderer::new(Palette::default_for(2)).render_rgba(&universe);
+    assert_golden("glider_default", universe.width(), universe.height(), &rgba);
+}
+
+#[test]
+fn test_rgba_custom_palette() {
+    let universe = glider();
+    let palette = Palette::new(vec![Color::new(10, 20, 30), Color::new(0, 200, 100)]);
+    let rgba = Renderer::new(palette).render_rgba(&universe);
+    assert_golden("glider_custom", universe.width(), universe.height(), &rgba);
+}
+
+#[test]
+fn test_rgba_generations_fade() {
+    let rule = Generations::parse("B2/S345/C6").unwrap();
+    let mut universe = Universe::with_states(6, 2, 6).unwrap();
+    for state in 0..6 {
+        universe.set_cell(state, 0, state as u8).unwrap();
+        universe.set_cell(5 - state, 1, state as u8).unwrap();
+    }
+    let rgba = Renderer::for_rule(&rule).render_rgba(&universe);
+    assert_golden("generations_fade", universe.width(), universe.height(), &rgba);
+}
+
+#[test]
+fn test_rgba_text() {
+    let mut universe = Universe::new(40, 9).unwrap();
+    universe.stamp_text("HI 42!", 1, 1, FontSize::Small).unwrap();
+    let rgba = Renderer::new(Palette::default_for(2)).render_rgba(&universe);
+    assert_golden("text_small", universe.width(), universe.height(), &rgba);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_png_region() {
+    use wasm_game_of_life::game_of_life::{Point, Rect, Size};
+
+    let universe = glider();
+    let path = std::env::temp_dir().join(format!("game-of-life-golden-{}.png", std::process::id()));
+    let rect = Rect::new(Point::new(1, 1), Size::new(4, 3));
+    Renderer::new(Palette::default_for(2)).export_png_region(&universe, rect, &path).unwrap();
+    let image = image::open(&path).unwrap().into_rgba8();
+    fs::remove_file(&path).unwrap();
+    assert_golden("glider_png_region", image.width() as usize, image.height() as usize, image.as_raw());
+}
+
+#[test]
+fn test_text_emoji() {
+    assert_golden_text("glider_emoji", &TextRenderer::emoji().render(&glider()).unwrap());
+}
+
+#[test]
+fn test_text_ascii() {
+    assert_golden_text("glider_ascii", &TextRenderer::ascii().render(&glider()).unwrap());
+}
+
+#[test]
+fn test_text_ascii_shrunk() {
+    let text = TextRenderer::ascii().with_max_width(Some(3)).render(&glider()).unwrap();
+    assert_golden_text("glider_ascii_shrunk", &text);
+}
+
+#[test]
+fn test_text_ansi() {
+    let text = TextRenderer::ascii().with_ansi(Some(Palette::default_for(2))).render(&glider()).unwrap();
+    assert_golden_text("glider_ansi", &text);
+}